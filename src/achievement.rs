@@ -0,0 +1,250 @@
+//! Achievement module
+//! Achievements are defined in a data file as a trigger and some conditions
+//! over the stats of the game. Game events are turned into triggers, and the
+//! achievements with those triggers are checked at the end of the frame
+//! Unlocked achievements, and what the stats of the save data don't have, are
+//! kept in their own file, so they survive the save data starting over
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub use self::list::{Achievement, AchievementList, Condition, Stat, Trigger};
+use crate::{
+    assets::{sync_asset_resource, RonAssetLoader},
+    data::{now, GameOptions, Persistent, PurchaseEvent, SaveData},
+    enemy::{DamageEvent, Element, KillEvent},
+    player::StatusEvent,
+    replay::is_playing,
+    AppConfig, GameState, PlayState,
+};
+
+pub mod list;
+
+// ······
+// Plugin
+// ······
+
+/// Achievements
+/// Checks the achievements when something happens and remembers the unlocked
+/// ones, sending `UnlockEvent` for the interface
+pub struct AchievementPlugin;
+
+impl Plugin for AchievementPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<AchievementList>()
+            .init_asset_loader::<RonAssetLoader<AchievementList>>()
+            .init_resource::<AchievementList>()
+            .add_event::<TriggerEvent>()
+            .add_event::<UnlockEvent>()
+            .add_systems(
+                OnEnter(GameState::Startup),
+                init_progress,
+            )
+            .add_systems(
+                OnEnter(PlayState::GameWon),
+                send_trigger(Trigger::Won),
+            )
+            .add_systems(
+                OnEnter(GameState::LevelTransition),
+                send_trigger(Trigger::Level),
+            )
+            .add_systems(OnEnter(GameState::End), reset_game)
+            .add_systems(
+                Update,
+                sync_asset_resource::<AchievementList>,
+            )
+            .add_systems(
+                PostUpdate,
+                check_achievements.run_if(not(is_playing)),
+            );
+    }
+}
+
+// ·········
+// Resources
+// ·········
+
+/// Unlocked achievements and the progress of the games in each slot
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct AchievementProgress {
+    /// Id of each unlocked achievement and when it was unlocked, in seconds
+    /// since the unix epoch
+    pub unlocked: BTreeMap<String, u64>,
+    /// Game being played in each save slot
+    pub slots: Vec<GameProgress>,
+}
+
+/// Stats of a game that the save data doesn't keep
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct GameProgress {
+    /// Elements the player attacked with
+    pub elements: Vec<Element>,
+}
+
+impl AchievementProgress {
+    pub fn game(&self, slot: usize) -> GameProgress {
+        self.slots.get(slot).cloned().unwrap_or_default()
+    }
+
+    pub fn set_game(&mut self, slot: usize, game: GameProgress) {
+        if self.slots.len() <= slot {
+            self.slots.resize(slot + 1, default());
+        }
+        self.slots[slot] = game;
+    }
+}
+
+// ······
+// Events
+// ······
+
+/// Checks the achievements with this trigger
+/// Other game events are turned into triggers as well
+#[derive(Event)]
+pub struct TriggerEvent(pub Trigger);
+
+/// An achievement was unlocked
+#[derive(Event)]
+pub struct UnlockEvent(pub Achievement);
+
+// ·······
+// Systems
+// ·······
+
+#[cfg(feature = "persist")]
+fn init_progress(mut cmd: Commands, config: Res<AppConfig>) {
+    let path = std::path::Path::new(config.data_dir);
+    let progress = Persistent::<AchievementProgress>::builder()
+        .name("achievements")
+        .format(bevy_persistent::StorageFormat::Toml)
+        .path(path.join("achievements.toml"))
+        .default(AchievementProgress::default())
+        .revertible(true)
+        .revert_to_default_on_deserialization_errors(true)
+        .build()
+        .expect("failed to initialize the achievements");
+    cmd.insert_resource(progress);
+}
+
+#[cfg(not(feature = "persist"))]
+fn init_progress(mut cmd: Commands, _config: Res<AppConfig>) {
+    cmd.insert_resource(Persistent(
+        AchievementProgress::default(),
+    ));
+}
+
+fn send_trigger(trigger: Trigger) -> impl Fn(EventWriter<TriggerEvent>) {
+    move |mut trigger_writer| {
+        trigger_writer.send(TriggerEvent(trigger));
+    }
+}
+
+/// Turns the events of this frame into triggers and unlocks the achievements
+/// that they complete
+fn check_achievements(
+    mut trigger_reader: EventReader<TriggerEvent>,
+    mut damage_reader: EventReader<DamageEvent>,
+    mut kill_reader: EventReader<KillEvent>,
+    mut status_reader: EventReader<StatusEvent>,
+    mut purchase_reader: EventReader<PurchaseEvent>,
+    list: Res<AchievementList>,
+    save_data: Res<Persistent<SaveData>>,
+    options: Res<Persistent<GameOptions>>,
+    mut progress: ResMut<Persistent<AchievementProgress>>,
+    mut unlock_writer: EventWriter<UnlockEvent>,
+) {
+    let slot = options.active_slot;
+    let mut game = progress.game(slot);
+    let mut triggers: Vec<_> = trigger_reader.read().map(|trigger| trigger.0).collect();
+
+    for damage in damage_reader.read() {
+        triggers.push(Trigger::Damage);
+        if !game.elements.contains(&damage.elem) {
+            game.elements.push(damage.elem);
+            let _ = progress.update(|progress| progress.set_game(slot, game.clone()));
+        }
+    }
+    triggers.extend(kill_reader.read().map(|_| Trigger::Kill));
+    triggers.extend(status_reader.read().map(|status| Trigger::Status(status.0)));
+    triggers.extend(purchase_reader.read().map(|_| Trigger::Purchase));
+    if triggers.is_empty() {
+        return;
+    }
+
+    let unlocked: Vec<_> = list
+        .achievements
+        .iter()
+        .filter(|achievement| !progress.unlocked.contains_key(&achievement.id))
+        .filter(|achievement| achievement.is_unlocked_by(&triggers, &save_data, &game))
+        .cloned()
+        .collect();
+    if unlocked.is_empty() {
+        return;
+    }
+
+    let time = now();
+    let _ = progress.update(|progress| {
+        for achievement in &unlocked {
+            progress.unlocked.insert(achievement.id.clone(), time);
+        }
+    });
+    for achievement in unlocked {
+        info!(
+            "achievement unlocked: {}",
+            achievement.name
+        );
+        unlock_writer.send(UnlockEvent(achievement));
+    }
+}
+
+/// The game was won, so the next one starts from nothing
+fn reset_game(
+    options: Res<Persistent<GameOptions>>,
+    mut progress: ResMut<Persistent<AchievementProgress>>,
+) {
+    let slot = options.active_slot;
+    let _ = progress.update(|progress| progress.set_game(slot, default()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestApp;
+
+    #[test]
+    fn progress_is_saved_as_toml() {
+        let mut progress = AchievementProgress::default();
+        progress.unlocked.insert("pacifist".into(), 10);
+        progress.set_game(1, GameProgress {
+            elements: vec![Element::Fire],
+        });
+
+        let file = toml::to_string(&progress).unwrap();
+        let loaded: AchievementProgress = toml::from_str(&file).unwrap();
+        assert_eq!(
+            loaded.unlocked.get("pacifist"),
+            Some(&10)
+        );
+        assert!(loaded.game(0).elements.is_empty());
+        assert_eq!(loaded.game(1).elements, [Element::Fire]);
+    }
+
+    #[test]
+    fn winning_without_kills_unlocks_pacifist() {
+        let mut game = TestApp::new();
+        game.start_level();
+        game.set_state(PlayState::GameWon);
+        game.update();
+
+        let progress = game
+            .app
+            .world()
+            .resource::<Persistent<AchievementProgress>>();
+        assert!(progress.unlocked.contains_key("pacifist"));
+        assert!(progress.unlocked.contains_key("deathless"));
+        // Nothing was attacked
+        assert!(!progress.unlocked.contains_key("one_element"));
+    }
+}
@@ -0,0 +1,129 @@
+//! Achievement list submodule
+//! What every achievement asks for, read from
+//! `assets/data/game.achievements.ron` so new ones can be added without code
+//! Each one is checked when something happens in the game, and it is unlocked
+//! if all of its conditions are met at that moment
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{achievement::GameProgress, assets::RonAsset, data::SaveData, player::Status};
+
+// ······
+// Assets
+// ······
+
+/// Every achievement in the game
+#[derive(Asset, Resource, TypePath, Deserialize, Clone, Debug, Default)]
+pub struct AchievementList {
+    pub achievements: Vec<Achievement>,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct Achievement {
+    /// Name it is saved with, it shouldn't change once it is released
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// When it is checked
+    pub trigger: Trigger,
+    /// What needs to be true when it is checked
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+}
+
+/// Something that happens in the game
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    /// The artifact was reached
+    Won,
+    /// A new level was reached through the ladder down
+    Level,
+    /// An upgrade was bought in the shop
+    Purchase,
+    /// The player attacked
+    Damage,
+    /// An enemy or pickup was killed
+    Kill,
+    /// The battery or connection of the player changed
+    Status(Status),
+}
+
+/// A stat that needs to be in a range, both ends included
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Condition {
+    pub stat: Stat,
+    #[serde(default)]
+    pub min: u32,
+    #[serde(default = "no_limit")]
+    pub max: u32,
+}
+
+fn no_limit() -> u32 {
+    u32::MAX
+}
+
+/// Something counted since the game started
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stat {
+    /// Animals killed, pickups don't count
+    Kills,
+    Deaths,
+    /// Level being played, the first one is 1
+    Depth,
+    LevelsCompleted,
+    Money,
+    /// Level of the battery upgrade, it starts at 1
+    BatteryLevel,
+    /// Levels of any upgrade bought
+    UpgradesBought,
+    /// Different elements attacked with, basic attacks included
+    ElementsUsed,
+}
+
+impl Stat {
+    pub fn value(self, save_data: &SaveData, game: &GameProgress) -> u32 {
+        match self {
+            Stat::Kills => save_data.enemies_killed,
+            Stat::Deaths => save_data.deaths,
+            Stat::Depth => save_data.level + 1,
+            Stat::LevelsCompleted => save_data.levels_completed,
+            Stat::Money => save_data.money,
+            Stat::BatteryLevel => save_data.battery_level as u32,
+            Stat::UpgradesBought => save_data.upgrades_bought().count() as u32,
+            Stat::ElementsUsed => game.elements.len() as u32,
+        }
+    }
+}
+
+impl Condition {
+    pub fn is_met(&self, save_data: &SaveData, game: &GameProgress) -> bool {
+        (self.min..=self.max).contains(&self.stat.value(save_data, game))
+    }
+}
+
+impl Achievement {
+    /// If it is checked with any of the triggers and all the conditions are met
+    pub fn is_unlocked_by(
+        &self,
+        triggers: &[Trigger],
+        save_data: &SaveData,
+        game: &GameProgress,
+    ) -> bool {
+        triggers.contains(&self.trigger)
+            && self
+                .conditions
+                .iter()
+                .all(|condition| condition.is_met(save_data, game))
+    }
+}
+
+impl RonAsset for AchievementList {
+    type File = Self;
+
+    const EXTENSION: &'static str = "achievements.ron";
+
+    fn from_file(file: Self) -> Result<Self, String> {
+        Ok(file)
+    }
+}
@@ -2,7 +2,15 @@
 
 use bevy::prelude::*;
 
-use crate::GameState;
+pub(crate) use self::loader::sync_asset_resource;
+pub use self::{
+    catalogue::{SpriteCatalogue, SpriteEntry},
+    loader::{RonAsset, RonAssetError, RonAssetLoader},
+};
+use crate::{achievement::AchievementList, enemy::ElementChart, tilemap::AuthoredLevel, GameState};
+
+pub mod catalogue;
+pub mod loader;
 
 pub const ATLAS_SIZE: (usize, usize) = (49, 23);
 
@@ -17,15 +25,57 @@ pub struct AssetLoaderPlugin;
 
 impl Plugin for AssetLoaderPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(LoadingData::default())
+        app.init_asset::<SpriteCatalogue>()
+            .init_asset_loader::<RonAssetLoader<SpriteCatalogue>>()
+            .init_resource::<SpriteCatalogue>()
+            .insert_resource(LoadingData::default())
             .add_systems(OnEnter(GameState::Startup), load_core)
             .add_systems(
                 OnEnter(GameState::Loading),
-                (load_sound, load_sprites),
+                (
+                    load_sound,
+                    load_sprites,
+                    load_levels,
+                    load_data,
+                ),
+            )
+            .add_systems(OnExit(GameState::Loading), insert_data)
+            .add_systems(
+                Update,
+                (
+                    sync_asset_resource::<SpriteCatalogue>,
+                    check_load_state.run_if(in_state(GameState::Loading)),
+                ),
+            );
+    }
+}
+
+/// Asset loader without a window or sound
+/// Sprites, sounds and fonts are left as empty handles, while levels, game
+/// data and the sprite catalogue are loaded as usual
+pub struct HeadlessAssetPlugin;
+
+impl Plugin for HeadlessAssetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<SpriteCatalogue>()
+            .init_asset_loader::<RonAssetLoader<SpriteCatalogue>>()
+            .init_resource::<SpriteCatalogue>()
+            .insert_resource(LoadingData::default())
+            .add_systems(
+                OnEnter(GameState::Startup),
+                empty_assets,
+            )
+            .add_systems(
+                OnEnter(GameState::Loading),
+                (load_levels, load_data),
             )
+            .add_systems(OnExit(GameState::Loading), insert_data)
             .add_systems(
                 Update,
-                check_load_state.run_if(in_state(GameState::Loading)),
+                (
+                    sync_asset_resource::<SpriteCatalogue>,
+                    check_load_state.run_if(in_state(GameState::Loading)),
+                ),
             );
     }
 }
@@ -72,6 +122,23 @@ pub struct SoundAssets {
     pub upgrades: Vec<Handle<AudioSource>>,
 }
 
+/// Hand made levels
+/// Each one declares the depth it replaces
+#[derive(Resource)]
+pub struct LevelAssets {
+    pub levels: Vec<Handle<AuthoredLevel>>,
+}
+
+/// Game balance data
+/// Kept as assets so it can be tweaked without recompiling
+#[derive(Resource)]
+pub struct DataAssets {
+    pub element_chart: Handle<ElementChart>,
+    pub achievements: Handle<AchievementList>,
+    /// Names of the sprites in the atlas
+    pub sprites: Handle<SpriteCatalogue>,
+}
+
 // ·······
 // Systems
 // ·······
@@ -87,6 +154,34 @@ pub(crate) fn load_core(mut cmd: Commands, asset_server: Res<AssetServer>) {
     cmd.insert_resource(assets);
 }
 
+fn empty_assets(mut cmd: Commands) {
+    let empty = |count: usize| vec![Handle::default(); count];
+    cmd.insert_resource(CoreAssets {
+        bevy_icon: default(),
+        kenney_icon: default(),
+        font: default(),
+    });
+    cmd.insert_resource(SpriteAssets {
+        one_bit: default(),
+        one_bit_atlas: default(),
+    });
+    // Same number of variations as the real ones, since they are picked by index
+    cmd.insert_resource(SoundAssets {
+        ambient_music: empty(5),
+        attack: default(),
+        boing: default(),
+        cat: empty(3),
+        chicken: empty(2),
+        clack: default(),
+        dog: empty(3),
+        low_battery: default(),
+        man: empty(2),
+        main_menu: default(),
+        steps: empty(2),
+        upgrades: empty(2),
+    });
+}
+
 fn load_sprites(
     mut cmd: Commands,
     asset_server: Res<AssetServer>,
@@ -146,6 +241,61 @@ fn load_sound(
     cmd.insert_resource(assets);
 }
 
+fn load_levels(
+    mut cmd: Commands,
+    asset_server: Res<AssetServer>,
+    mut loading_data: ResMut<LoadingData>,
+) {
+    // New authored levels need to be listed here
+    let levels = ["levels/final.level.ron"];
+    let assets = LevelAssets {
+        levels: loading_data.load_vec(&asset_server, &levels),
+    };
+
+    cmd.insert_resource(assets);
+}
+
+fn load_data(
+    mut cmd: Commands,
+    asset_server: Res<AssetServer>,
+    mut loading_data: ResMut<LoadingData>,
+) {
+    let assets = DataAssets {
+        element_chart: loading_data.load(&asset_server, "data/elements.chart.ron"),
+        achievements: loading_data.load(
+            &asset_server,
+            "data/game.achievements.ron",
+        ),
+        sprites: loading_data.load(
+            &asset_server,
+            "data/sprites.catalogue.ron",
+        ),
+    };
+
+    cmd.insert_resource(assets);
+}
+
+/// Copies the game data into its resources before the game starts
+/// `sync_asset_resource` only sees the load event on the next frame, so the
+/// first level would be built with the default data
+fn insert_data(
+    mut cmd: Commands,
+    data: Res<DataAssets>,
+    element_charts: Res<Assets<ElementChart>>,
+    achievement_lists: Res<Assets<AchievementList>>,
+    sprite_catalogues: Res<Assets<SpriteCatalogue>>,
+) {
+    if let Some(chart) = element_charts.get(&data.element_chart) {
+        cmd.insert_resource(chart.clone());
+    }
+    if let Some(achievements) = achievement_lists.get(&data.achievements) {
+        cmd.insert_resource(achievements.clone());
+    }
+    if let Some(sprites) = sprite_catalogues.get(&data.sprites) {
+        cmd.insert_resource(sprites.clone());
+    }
+}
+
 // ·······
 // Helpers
 // ·······
@@ -208,19 +358,22 @@ impl LoadingData {
     }
 }
 
+/// The loading screen is missing when running headless
 fn check_load_state(
-    #[cfg(feature = "loading")] curr_loading_state: Res<
-        State<crate::ui::loading::LoadingScreenState>,
+    #[cfg(feature = "loading")] curr_loading_state: Option<
+        Res<State<crate::ui::loading::LoadingScreenState>>,
     >,
     mut next_state: ResMut<NextState<GameState>>,
     mut loading_data: ResMut<LoadingData>,
     asset_server: Res<AssetServer>,
 ) {
     #[cfg(feature = "loading")]
-    if !matches!(
-        curr_loading_state.get(),
-        crate::ui::loading::LoadingScreenState::Loading
-    ) {
+    if curr_loading_state.is_some_and(|state| {
+        !matches!(
+            state.get(),
+            crate::ui::loading::LoadingScreenState::Loading
+        )
+    }) {
         return;
     }
 
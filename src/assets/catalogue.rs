@@ -0,0 +1,232 @@
+//! Sprite catalogue submodule
+//! Names every sprite of the game with its cells in the atlas, read from
+//! `assets/data/sprites.catalogue.ron` so the game can be reskinned by editing
+//! a single file
+//! Cells are checked against `ATLAS_SIZE` when the file is loaded
+
+use std::{collections::HashMap, fmt};
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::assets::{RonAsset, ATLAS_SIZE};
+
+/// Sprites the game asks for, the catalogue can't be loaded without them
+const REQUIRED: [&str; 24] = [
+    "player",
+    "enemy.chicken",
+    "enemy.cat",
+    "enemy.dog",
+    "enemy.young_old",
+    "enemy.man",
+    "enemy.money",
+    "enemy.battery",
+    "enemy.end_game",
+    "tile.empty",
+    "tile.ground.shallow",
+    "tile.ground.deep",
+    "tile.path",
+    "tile.wall.shallow",
+    "tile.wall.deep",
+    "tile.ladder.down",
+    "tile.ladder.up",
+    "hud.connection",
+    "hud.battery",
+    "hud.money",
+    "attack.basic",
+    "attack.fire",
+    "attack.water",
+    "attack.grass",
+];
+
+/// Returned for names that are not in the catalogue, before it is loaded
+static MISSING: SpriteEntry = SpriteEntry {
+    cells: Vec::new(),
+    tint: None,
+};
+
+// ······
+// Assets
+// ······
+
+/// Every named sprite in the atlas
+#[derive(Asset, Resource, TypePath, Deserialize, Clone, Debug, Default)]
+pub struct SpriteCatalogue {
+    pub sprites: HashMap<String, SpriteEntry>,
+}
+
+/// Cells of a sprite in the atlas
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct SpriteEntry {
+    /// Column and row of each variation or animation frame
+    /// A cell can be repeated to make it more likely to be picked
+    pub cells: Vec<(usize, usize)>,
+    /// Color of the sprite, only used by tiles and hud icons
+    #[serde(default)]
+    pub tint: Option<Color>,
+}
+
+impl SpriteEntry {
+    /// Atlas index of a variation, wrapping around the ones available
+    pub fn frame(&self, variation: usize) -> usize {
+        if self.cells.is_empty() {
+            return 0;
+        }
+        let (x, y) = self.cells[variation % self.cells.len()];
+        y * ATLAS_SIZE.0 + x
+    }
+
+    /// Atlas index of a random variation
+    /// The rng is only used if there is more than one
+    pub fn pick(&self, rng: &mut impl Rng) -> usize {
+        match self.cells.len() {
+            0 | 1 => self.frame(0),
+            len => self.frame(rng.gen_range(0..len)),
+        }
+    }
+
+    pub fn tint(&self) -> Color {
+        self.tint.unwrap_or(Color::WHITE)
+    }
+}
+
+impl SpriteCatalogue {
+    pub fn get(&self, name: &str) -> &SpriteEntry {
+        self.sprites.get(name).unwrap_or(&MISSING)
+    }
+
+    /// Atlas index of the first variation of a sprite
+    pub fn index(&self, name: &str) -> usize {
+        self.get(name).frame(0)
+    }
+
+    /// Checks that the sprites the game uses exist and fit in the atlas
+    fn validate(&self) -> Result<(), SpriteCatalogueError> {
+        if let Some(name) = REQUIRED
+            .iter()
+            .find(|name| !self.sprites.contains_key(**name))
+        {
+            return Err(SpriteCatalogueError::Missing(
+                name.to_string(),
+            ));
+        }
+        for (name, entry) in &self.sprites {
+            if entry.cells.is_empty() {
+                return Err(SpriteCatalogueError::Empty(
+                    name.clone(),
+                ));
+            }
+            if let Some(&cell) = entry
+                .cells
+                .iter()
+                .find(|(x, y)| *x >= ATLAS_SIZE.0 || *y >= ATLAS_SIZE.1)
+            {
+                return Err(SpriteCatalogueError::OutOfAtlas(
+                    name.clone(),
+                    cell,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RonAsset for SpriteCatalogue {
+    type File = Self;
+
+    const EXTENSION: &'static str = "catalogue.ron";
+
+    fn from_file(file: Self) -> Result<Self, String> {
+        file.validate().map_err(|err| err.to_string())?;
+        Ok(file)
+    }
+}
+
+/// What can be wrong in the catalogue
+#[derive(Debug)]
+pub enum SpriteCatalogueError {
+    /// A sprite that the game uses is not defined
+    Missing(String),
+    /// A sprite has no cells
+    Empty(String),
+    /// A cell of a sprite is outside of the atlas
+    OutOfAtlas(String, (usize, usize)),
+}
+
+impl fmt::Display for SpriteCatalogueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing(name) => write!(
+                f,
+                "sprite \"{}\" is missing from the catalogue",
+                name
+            ),
+            Self::Empty(name) => write!(f, "sprite \"{}\" has no cells", name),
+            Self::OutOfAtlas(name, (x, y)) => write!(
+                f,
+                "sprite \"{}\" has cell ({}, {}) outside of the {}x{} atlas",
+                name, x, y, ATLAS_SIZE.0, ATLAS_SIZE.1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SpriteCatalogueError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{player::Player, testing::TestApp};
+
+    #[test]
+    fn catalogue_file_is_valid() {
+        let bytes = std::fs::read("assets/data/sprites.catalogue.ron").unwrap();
+        let catalogue: SpriteCatalogue = ron::de::from_bytes(&bytes).unwrap();
+        catalogue.validate().unwrap();
+
+        assert_eq!(
+            catalogue.index("enemy.chicken"),
+            7 * ATLAS_SIZE.0 + 25
+        );
+        assert_eq!(
+            catalogue.get("hud.battery").frame(3),
+            22 * ATLAS_SIZE.0 + 7
+        );
+    }
+
+    #[test]
+    fn cells_outside_of_the_atlas_are_rejected() {
+        let mut catalogue = SpriteCatalogue::default();
+        for name in REQUIRED {
+            catalogue.sprites.insert(name.into(), SpriteEntry {
+                cells: vec![(0, 0)],
+                tint: None,
+            });
+        }
+        assert!(catalogue.validate().is_ok());
+
+        catalogue
+            .sprites
+            .get_mut("player")
+            .unwrap()
+            .cells
+            .push(ATLAS_SIZE);
+        assert!(matches!(
+            catalogue.validate(),
+            Err(SpriteCatalogueError::OutOfAtlas(..))
+        ));
+    }
+
+    #[test]
+    fn level_sprites_come_from_the_catalogue() {
+        let mut game = TestApp::new();
+        game.start_level();
+
+        let world = game.app.world_mut();
+        let atlas = world
+            .query_filtered::<&TextureAtlas, With<Player>>()
+            .single(world);
+        assert_eq!(atlas.index, 25);
+    }
+}
@@ -0,0 +1,112 @@
+//! RON loader submodule
+//! Game data is written as RON files that are loaded as assets, so they can be
+//! tweaked without recompiling and are hot reloaded while the game runs
+//! Most of them are also kept as a resource, copied from the asset every time
+//! it loads
+
+use std::{fmt, marker::PhantomData};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+};
+use serde::de::DeserializeOwned;
+
+/// An asset read from a RON file
+pub trait RonAsset: Asset + Sized {
+    /// What is written in the file, usually the asset itself
+    type File: DeserializeOwned;
+    /// Ending of the file names, such as `chart.ron`
+    const EXTENSION: &'static str;
+
+    /// Builds the asset from the file, failing if it isn't valid
+    fn from_file(file: Self::File) -> Result<Self, String>;
+}
+
+// ······
+// Loader
+// ······
+
+pub struct RonAssetLoader<T>(PhantomData<T>);
+
+impl<T> Default for RonAssetLoader<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: RonAsset> AssetLoader for RonAssetLoader<T> {
+    type Asset = T;
+    type Error = RonAssetError;
+    type Settings = ();
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<T, RonAssetError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let file: T::File = ron::de::from_bytes(&bytes)?;
+        T::from_file(file).map_err(RonAssetError::Invalid)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        std::slice::from_ref(&T::EXTENSION)
+    }
+}
+
+#[derive(Debug)]
+pub enum RonAssetError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    /// The file was read, but what it says can't be used
+    Invalid(String),
+}
+
+impl fmt::Display for RonAssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not read file: {}", err),
+            Self::Ron(err) => write!(f, "could not parse file: {}", err),
+            Self::Invalid(err) => write!(f, "invalid file: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for RonAssetError {}
+
+impl From<std::io::Error> for RonAssetError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for RonAssetError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        Self::Ron(err)
+    }
+}
+
+// ·······
+// Systems
+// ·······
+
+/// Copies an asset into the resource of the same type when it is loaded or
+/// changes on disk
+pub(crate) fn sync_asset_resource<T: Asset + Resource + Clone>(
+    mut cmd: Commands,
+    mut asset_events: EventReader<AssetEvent<T>>,
+    assets: Res<Assets<T>>,
+) {
+    for event in asset_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+        if let Some(asset) = assets.get(*id) {
+            cmd.insert_resource(asset.clone());
+        }
+    }
+}
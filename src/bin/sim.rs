@@ -0,0 +1,94 @@
+//! Headless simulation
+//! Plays runs with a bot and outputs stats of each one
+//! Nothing is kept between simulations, so the same seed repeats the same runs
+//! Usage: sim [--runs N] [--seed N] [--bot greedy|random] [--json] [--out PATH]
+//!            [--balance PATH]
+//! The balance file is RON with any of the fields of `Balance`, for example
+//! `(price: (5, 10, 15, 20, 25, 30, 35, 40, 45, 50, 55), battery: (50, 50))`
+
+use std::time::Duration;
+
+use bevy::{app::ScheduleRunnerPlugin, prelude::*, state::app::StatesPlugin};
+use kenney_jam::{
+    assets::HeadlessAssetPlugin,
+    data::Balance,
+    sim::{Bot, SimPlugin, StatsFormat},
+    AppConfig, LogicPlugin,
+};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let arg = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+    };
+
+    let balance = match arg("--balance") {
+        Some(path) => match read_balance(path) {
+            Ok(balance) => balance,
+            Err(err) => {
+                eprintln!(
+                    "failed to read the balance from {}: {}",
+                    path, err
+                );
+                std::process::exit(1);
+            },
+        },
+        None => Balance::default(),
+    };
+
+    let sim = SimPlugin {
+        runs: arg("--runs")
+            .and_then(|runs| runs.parse().ok())
+            .unwrap_or(10),
+        seed: arg("--seed")
+            .and_then(|seed| seed.parse().ok())
+            .unwrap_or_else(rand::random),
+        bot: match arg("--bot").map(String::as_str) {
+            Some("random") => Bot::Random,
+            _ => Bot::Greedy,
+        },
+        format: if args.iter().any(|arg| arg == "--json") {
+            StatsFormat::Json
+        } else {
+            StatsFormat::Csv
+        },
+        out: arg("--out").map(Into::into),
+        balance,
+    };
+
+    // Every simulation starts from a new save, in a folder of its own
+    let data_dir = std::env::temp_dir().join(format!(
+        "kenney-jam-sim-{}",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&data_dir);
+    let data_dir: &'static str =
+        Box::leak(data_dir.to_string_lossy().into_owned().into_boxed_str());
+
+    App::new()
+        .insert_resource(AppConfig {
+            data_dir,
+            record_replays: false,
+            ..default()
+        })
+        .add_plugins((
+            MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(
+                Duration::ZERO,
+            )),
+            AssetPlugin::default(),
+            StatesPlugin,
+            HeadlessAssetPlugin,
+            LogicPlugin,
+            sim,
+        ))
+        .run();
+
+    let _ = std::fs::remove_dir_all(data_dir);
+}
+
+fn read_balance(path: &str) -> Result<Balance, Box<dyn std::error::Error>> {
+    let file = std::fs::read_to_string(path)?;
+    Ok(ron::from_str(&file)?)
+}
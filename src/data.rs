@@ -1,5 +1,7 @@
 //! Data persistence module
 
+use std::path::{Path, PathBuf};
+
 use bevy::prelude::*;
 #[cfg(feature = "persist")]
 pub use bevy_persistent::prelude::Persistent;
@@ -7,7 +9,25 @@ use serde::{Deserialize, Serialize};
 
 #[cfg(not(feature = "persist"))]
 pub use self::alt::Persistent;
-use crate::{enemy::Element, GameState, PlayState};
+use self::{migration::SAVE_VERSION, suspend::Suspended};
+use crate::{
+    achievement::AchievementProgress,
+    enemy::{Element, WEIGHTS},
+    AppConfig, GameState, PlayState,
+};
+
+pub mod history;
+pub mod migration;
+pub mod suspend;
+
+/// Turns that can be undone in a run, unless the assist option is enabled
+pub const UNDOS_PER_RUN: u32 = 3;
+/// Money needed for each level of an upgrade, unless `Balance` changes it
+pub const PRICE: [u32; 11] = [5, 10, 15, 30, 40, 50, 60, 70, 100, 200, 999];
+/// Highest level of the range, battery and attack upgrades
+pub const MAX_UPGRADE: usize = 10;
+/// Number of save slots, each one is stored in its own file
+pub const SAVE_SLOTS: usize = 3;
 
 // ······
 // Plugin
@@ -20,13 +40,25 @@ pub struct DataPlugin;
 
 impl Plugin for DataPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<RestartEvent>()
+        app.init_resource::<Balance>()
+            .add_event::<RestartEvent>()
+            .add_event::<PurchaseEvent>()
+            .add_event::<SlotEvent>()
+            .add_plugins(history::HistoryPlugin)
             .add_systems(OnEnter(GameState::Startup), init_data)
             .add_systems(
                 OnEnter(GameState::Play),
-                restart.run_if(run_once()),
+                (
+                    restart.run_if(run_once().and_then(not(resource_exists::<Suspended>))),
+                    touch_slot,
+                ),
             )
-            .add_systems(Update, on_restart);
+            .add_systems(OnEnter(GameState::Reload), restart)
+            .add_systems(OnEnter(PlayState::GameOver), lose_run)
+            .add_systems(Update, (on_restart, on_slot));
+
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_plugins(suspend::SuspendPlugin);
     }
 }
 
@@ -47,6 +79,20 @@ pub struct GameOptions {
     /// Controlls if text to speech is enabled for menu navigation
     #[cfg(feature = "tts")]
     pub text_to_speech: bool,
+
+    /// Seconds a direction is held before the player starts walking on its own
+    #[serde(default = "default_repeat_delay")]
+    pub repeat_delay: f32,
+    /// Seconds between steps while a direction is held
+    #[serde(default = "default_repeat_rate")]
+    pub repeat_rate: f32,
+    /// Assist option, turns can be undone without limit
+    #[serde(default)]
+    pub unlimited_undo: bool,
+
+    /// Save slot that is being played
+    #[serde(default)]
+    pub active_slot: usize,
 }
 
 impl Default for GameOptions {
@@ -56,16 +102,34 @@ impl Default for GameOptions {
             accent_color: Color::srgb(0.3, 0.5, 0.9),
             #[cfg(feature = "tts")]
             text_to_speech: default(),
+            repeat_delay: default_repeat_delay(),
+            repeat_rate: default_repeat_rate(),
+            unlimited_undo: false,
+            active_slot: 0,
         }
     }
 }
 
+fn default_repeat_delay() -> f32 {
+    0.25
+}
+
+fn default_repeat_rate() -> f32 {
+    0.15
+}
+
 /// Save data
 /// A place to save the player's progress
 /// CHANGE: Add relevant save data here
-#[derive(Resource, Serialize, Deserialize)]
+#[derive(Resource, Serialize, Deserialize, Clone)]
 pub struct SaveData {
+    /// Version of the struct, older saves are migrated before loading them
+    #[serde(default)]
+    pub version: u32,
     pub level: u32,
+    /// Seed of the current run, every level is generated from it
+    #[serde(default, with = "seed_format")]
+    pub seed: u64,
     pub battery: u32,
     pub range_level: usize,
     pub battery_level: usize,
@@ -77,16 +141,25 @@ pub struct SaveData {
     pub water_uses: u32,
     pub grass_uses: u32,
     pub attack_selected: Element,
+    /// Turns that can still be undone in this run
+    #[serde(default)]
+    pub undos_left: u32,
     pub money: u32,
     pub enemies_killed: u32,
     pub levels_completed: u32,
     pub deaths: u32,
+    /// Seconds since the unix epoch when the slot was last played, 0 if it
+    /// wasn't or the clock isn't available
+    #[serde(default)]
+    pub last_played: u64,
 }
 
 impl Default for SaveData {
     fn default() -> Self {
         Self {
+            version: SAVE_VERSION,
             level: 0,
+            seed: 0,
             battery: 200,
             range_level: 1,
             battery_level: 1,
@@ -98,27 +171,167 @@ impl Default for SaveData {
             water_uses: 0,
             grass_uses: 0,
             attack_selected: Element::Basic,
+            undos_left: UNDOS_PER_RUN,
             money: 0,
             enemies_killed: 0,
             levels_completed: 0,
             deaths: 0,
+            last_played: 0,
         }
     }
 }
 
+/// Something that can be bought in the shop
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Upgrade {
+    Range,
+    Battery,
+    Basic,
+    Fire,
+    Water,
+    Grass,
+}
+
+impl Upgrade {
+    pub const ALL: [Upgrade; 6] = [
+        Upgrade::Range,
+        Upgrade::Battery,
+        Upgrade::Basic,
+        Upgrade::Fire,
+        Upgrade::Water,
+        Upgrade::Grass,
+    ];
+}
+
+impl SaveData {
+    pub fn upgrade_level(&self, upgrade: Upgrade) -> usize {
+        match upgrade {
+            Upgrade::Range => self.range_level,
+            Upgrade::Battery => self.battery_level,
+            Upgrade::Basic => self.attack_level,
+            Upgrade::Fire => self.fire as usize,
+            Upgrade::Water => self.water as usize,
+            Upgrade::Grass => self.grass as usize,
+        }
+    }
+
+    fn set_upgrade_level(&mut self, upgrade: Upgrade, level: usize) {
+        match upgrade {
+            Upgrade::Range => self.range_level = level,
+            Upgrade::Battery => self.battery_level = level,
+            Upgrade::Basic => self.attack_level = level,
+            Upgrade::Fire => self.fire = level as u32,
+            Upgrade::Water => self.water = level as u32,
+            Upgrade::Grass => self.grass = level as u32,
+        }
+    }
+
+    /// Cost of the next level of an upgrade, `None` if it can't go higher
+    /// Elements have no maximum level, only the prices that are left
+    pub fn price(&self, upgrade: Upgrade, balance: &Balance) -> Option<u32> {
+        let level = self.upgrade_level(upgrade);
+        let is_element = matches!(
+            upgrade,
+            Upgrade::Fire | Upgrade::Water | Upgrade::Grass
+        );
+        if !is_element && level >= MAX_UPGRADE {
+            return None;
+        }
+        balance.price.get(level).copied()
+    }
+
+    /// Pays for the next level of an upgrade, returns false if it can't
+    pub fn buy(&mut self, upgrade: Upgrade, balance: &Balance) -> bool {
+        match self.price(upgrade, balance) {
+            Some(price) if self.money >= price => {
+                self.money -= price;
+                self.set_upgrade_level(upgrade, self.upgrade_level(upgrade) + 1);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// One entry for each level bought of each upgrade, not counting the ones
+    /// that every save starts with
+    pub fn upgrades_bought(&self) -> impl Iterator<Item = Upgrade> + '_ {
+        let start = SaveData::default();
+        Upgrade::ALL.into_iter().flat_map(move |upgrade| {
+            let bought = self
+                .upgrade_level(upgrade)
+                .saturating_sub(start.upgrade_level(upgrade));
+            std::iter::repeat_n(upgrade, bought)
+        })
+    }
+
+    /// If nothing has been done with this save yet
+    pub fn is_new(&self) -> bool {
+        self.levels_completed == 0
+            && self.deaths == 0
+            && self.money == 0
+            && self.enemies_killed == 0
+    }
+
+    /// Gives back what the last level of an upgrade cost
+    pub fn sell(&mut self, upgrade: Upgrade, balance: &Balance) -> bool {
+        let level = self.upgrade_level(upgrade);
+        if level == 0 {
+            return false;
+        }
+        self.set_upgrade_level(upgrade, level - 1);
+        self.money += balance.price[level - 1];
+        true
+    }
+}
+
 #[inline]
 pub fn max_range(level: usize) -> u32 {
     (4 + level) as u32
 }
 
+/// How many tiles away the player can see
 #[inline]
-pub fn max_battery(level: usize) -> u32 {
-    (25 + level * 50) as u32
+pub fn sight_radius(level: usize) -> u32 {
+    (5 + level) as u32
 }
 
-#[inline]
-pub fn attack(level: usize) -> f32 {
-    0.3 + level as f32 * 0.3
+/// Numbers that tune how hard the game is
+/// They default to the constants, the simulation can replace them with a file
+/// to compare different values
+#[derive(Resource, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Balance {
+    /// Chance out of 100 of each enemy type, for each level
+    pub weights: [[u32; 7]; 12],
+    /// Money needed for each level of an upgrade
+    pub price: [u32; 11],
+    /// Battery without upgrades, and how much each level of the upgrade adds
+    pub battery: (u32, u32),
+    /// Damage without upgrades, and how much each level of the upgrade adds
+    pub attack: (f32, f32),
+}
+
+impl Default for Balance {
+    fn default() -> Self {
+        Self {
+            weights: WEIGHTS,
+            price: PRICE,
+            battery: (25, 50),
+            attack: (0.3, 0.3),
+        }
+    }
+}
+
+impl Balance {
+    #[inline]
+    pub fn max_battery(&self, level: usize) -> u32 {
+        self.battery.0 + level as u32 * self.battery.1
+    }
+
+    #[inline]
+    pub fn attack(&self, level: usize) -> f32 {
+        self.attack.0 + level as f32 * self.attack.1
+    }
 }
 
 /// When persist is not enabled, this wrapper just serves
@@ -165,71 +378,491 @@ mod alt {
 #[derive(Event)]
 pub struct RestartEvent;
 
+/// An upgrade was bought in the shop
+#[derive(Event)]
+pub struct PurchaseEvent(pub Upgrade);
+
+/// Something to do with a save slot, asked from the saves menu
+#[derive(Event, Clone, Copy)]
+pub enum SlotEvent {
+    /// Plays the slot, the run starts again with its save data
+    Switch(usize),
+    /// Copies the slot to the first empty one, if there is any
+    Copy(usize),
+    /// Resets the slot, the active one can't be deleted
+    Delete(usize),
+}
+
 // ·······
 // Systems
 // ·······
 
 #[cfg(feature = "persist")]
-pub(crate) fn init_data(mut cmd: Commands) {
-    let path = std::path::Path::new(if cfg!(target_arch = "wasm32") { "local" } else { ".data" });
+pub(crate) fn init_data(mut cmd: Commands, config: Res<AppConfig>) {
+    let path = std::path::Path::new(config.data_dir);
     info!("{:?}", path);
 
-    cmd.insert_resource(
-        Persistent::<GameOptions>::builder()
-            .name("game options")
-            .format(bevy_persistent::StorageFormat::Toml)
-            .path(path.join("options.toml"))
-            .default(GameOptions::default())
-            .revertible(true)
-            .revert_to_default_on_deserialization_errors(true)
-            .build()
-            .expect("failed to initialize game options"),
-    );
+    let options = Persistent::<GameOptions>::builder()
+        .name("game options")
+        .format(bevy_persistent::StorageFormat::Toml)
+        .path(path.join("options.toml"))
+        .default(GameOptions::default())
+        .revertible(true)
+        .revert_to_default_on_deserialization_errors(true)
+        .build()
+        .expect("failed to initialize game options");
 
-    cmd.insert_resource(
-        Persistent::<SaveData>::builder()
-            .name("save data")
-            .format(bevy_persistent::StorageFormat::Toml)
-            .path(path.join("save.toml"))
-            .default(SaveData::default())
-            .revertible(true)
-            .revert_to_default_on_deserialization_errors(true)
-            .build()
-            .expect("failed to initialize save data"),
-    );
+    cmd.insert_resource(open_slot(&config, options.active_slot));
+    cmd.insert_resource(options);
 }
 
 pub(crate) fn restart(mut reset_writer: EventWriter<RestartEvent>) {
     reset_writer.send(RestartEvent);
 }
 
+/// Keeps track of when the active slot was played
+fn touch_slot(mut save_data: ResMut<Persistent<SaveData>>) {
+    let now = now();
+    let _ = save_data.update(|data| data.last_played = now);
+}
+
 #[cfg(not(feature = "persist"))]
 pub(crate) fn init_data(mut cmd: Commands) {
     cmd.insert_resource(Persistent(GameOptions::default()));
     cmd.insert_resource(Persistent(SaveData::default()));
 }
 
-fn on_restart(
+/// Running out of battery costs half of the money
+pub(crate) fn lose_run(mut save_data: ResMut<Persistent<SaveData>>) {
+    save_data.money /= 2;
+    save_data.deaths += 1;
+}
+
+pub(crate) fn on_restart(
     mut next_state: ResMut<NextState<GameState>>,
     mut next_play_state: ResMut<NextState<PlayState>>,
     mut save_data: ResMut<Persistent<SaveData>>,
     mut restart_reader: EventReader<RestartEvent>,
-    mut first: Local<bool>,
+    state: Res<State<GameState>>,
+    balance: Res<Balance>,
 ) {
     if restart_reader.read().next().is_some() {
-        next_state.set(GameState::Play);
-        if !*first {
-            *first = true
-        } else {
+        // The game starts with a restart, and it stays in the menu
+        if state.get() != &GameState::Play {
             next_play_state.set(PlayState::Play);
         }
-        let battery = max_battery(save_data.battery_level);
+        next_state.set(GameState::Play);
+        let battery = balance.max_battery(save_data.battery_level);
+        let seed = rand::random();
         let _ = save_data.update(|data| {
             data.level = 0;
+            data.seed = seed;
             data.battery = battery;
             data.fire_uses = data.fire;
             data.water_uses = data.water;
             data.grass_uses = data.grass;
+            data.undos_left = UNDOS_PER_RUN;
         });
     }
 }
+
+pub(crate) fn on_slot(
+    mut cmd: Commands,
+    config: Res<AppConfig>,
+    mut options: ResMut<Persistent<GameOptions>>,
+    save_data: Res<Persistent<SaveData>>,
+    mut achievements: ResMut<Persistent<AchievementProgress>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut next_play_state: ResMut<NextState<PlayState>>,
+    mut slot_reader: EventReader<SlotEvent>,
+) {
+    for event in slot_reader.read() {
+        match *event {
+            SlotEvent::Switch(slot) if slot == options.active_slot => {
+                next_play_state.set(PlayState::Play);
+            },
+            SlotEvent::Switch(slot) => {
+                let _ = options.update(|options| options.active_slot = slot);
+                cmd.insert_resource(open_slot(&config, slot));
+                next_state.set(GameState::Reload);
+            },
+            SlotEvent::Copy(slot) => {
+                let source = match slot == options.active_slot {
+                    true => SaveData::clone(&save_data),
+                    false => peek_slot(&config, slot),
+                };
+                let Some(target) = (0..SAVE_SLOTS)
+                    .filter(|target| *target != slot && *target != options.active_slot)
+                    .find(|target| peek_slot(&config, *target).is_new())
+                else {
+                    info!("there is no empty slot to copy to");
+                    continue;
+                };
+                let _ = open_slot(&config, target).update(|data| *data = source.clone());
+                let game = achievements.game(slot);
+                let _ = achievements.update(|progress| progress.set_game(target, game.clone()));
+            },
+            SlotEvent::Delete(slot) if slot == options.active_slot => {
+                warn!("the slot being played can't be deleted");
+            },
+            SlotEvent::Delete(slot) => {
+                let _ = open_slot(&config, slot).update(|data| *data = SaveData::default());
+                let _ = achievements.update(|progress| progress.set_game(slot, default()));
+                #[cfg(not(target_arch = "wasm32"))]
+                suspend::remove_file(&config, slot);
+            },
+        }
+    }
+}
+
+// ·······
+// Helpers
+// ·······
+
+/// Save data of a slot, an empty one is created if it doesn't exist
+/// The first slot uses the file from before there were slots
+#[cfg(feature = "persist")]
+pub fn open_slot(config: &AppConfig, slot: usize) -> Persistent<SaveData> {
+    let slot = slot.min(SAVE_SLOTS - 1);
+    let path = slot_path(config, slot, "save", "toml");
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Err(err) = migration::migrate_file(&path) {
+        error!("failed to migrate save data: {}", err);
+    }
+
+    Persistent::<SaveData>::builder()
+        .name(format!("save data {}", slot + 1))
+        .format(bevy_persistent::StorageFormat::Toml)
+        .path(path)
+        .default(SaveData::default())
+        .revertible(true)
+        .revert_to_default_on_deserialization_errors(true)
+        .build()
+        .expect("failed to initialize save data")
+}
+
+#[cfg(not(feature = "persist"))]
+pub fn open_slot(_config: &AppConfig, _slot: usize) -> Persistent<SaveData> {
+    Persistent(SaveData::default())
+}
+
+/// Save data of a slot, reading its file without creating or migrating it
+/// Older saves are migrated only in memory, missing or invalid ones are new
+#[cfg(all(feature = "persist", not(target_arch = "wasm32")))]
+pub fn peek_slot(config: &AppConfig, slot: usize) -> SaveData {
+    let path = slot_path(
+        config,
+        slot.min(SAVE_SLOTS - 1),
+        "save",
+        "toml",
+    );
+    let Ok(file) = std::fs::read_to_string(path) else { return default() };
+    let Ok(mut save) = file.parse::<toml::Table>() else { return default() };
+    migration::migrate(&mut save);
+    toml::Value::Table(save).try_into().unwrap_or_default()
+}
+
+/// Local storage is only read through `Persistent`, so the slot is opened
+#[cfg(all(feature = "persist", target_arch = "wasm32"))]
+pub fn peek_slot(config: &AppConfig, slot: usize) -> SaveData {
+    SaveData::clone(&open_slot(config, slot))
+}
+
+#[cfg(not(feature = "persist"))]
+pub fn peek_slot(_config: &AppConfig, _slot: usize) -> SaveData {
+    SaveData::default()
+}
+
+/// File of a slot in the data folder
+/// The first slot has no number, so it uses the file from before there were
+/// slots
+pub fn slot_path(config: &AppConfig, slot: usize, name: &str, extension: &str) -> PathBuf {
+    let file = match slot {
+        0 => format!("{}.{}", name, extension),
+        _ => format!("{}-{}.{}", name, slot + 1, extension),
+    };
+    Path::new(config.data_dir).join(file)
+}
+
+/// Seconds since the unix epoch, 0 on the web where there is no clock
+pub fn now() -> u64 {
+    #[cfg(not(target_arch = "wasm32"))]
+    return std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    #[cfg(target_arch = "wasm32")]
+    0
+}
+
+/// Seeds are saved as `i64` with the same bits, since toml can't hold
+/// integers above `i64::MAX`
+/// Files written before that, with the seed as an unsigned number, still load
+pub mod seed_format {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Seed {
+        Signed(i64),
+        Unsigned(u64),
+    }
+
+    pub fn serialize<S: Serializer>(seed: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(*seed as i64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        Ok(match Seed::deserialize(deserializer)? {
+            Seed::Signed(seed) => seed as u64,
+            Seed::Unsigned(seed) => seed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{achievement::GameProgress, testing::TestApp};
+
+    #[test]
+    fn any_seed_is_saved_as_toml() {
+        for seed in [0, 42, i64::MAX as u64 + 1, u64::MAX] {
+            let save_data = SaveData { seed, ..default() };
+
+            let file = toml::to_string(&save_data).unwrap();
+            let loaded: SaveData = toml::from_str(&file).unwrap();
+            assert_eq!(loaded.seed, seed);
+        }
+        // Replays and suspended levels written with the seed as an unsigned number
+        #[derive(Deserialize)]
+        struct Seeded {
+            #[serde(with = "seed_format")]
+            seed: u64,
+        }
+        let loaded: Seeded = ron::from_str(&format!("(seed: {})", u64::MAX)).unwrap();
+        assert_eq!(loaded.seed, u64::MAX);
+    }
+
+    #[test]
+    fn balance_files_only_change_what_they_set() {
+        let balance: Balance = ron::from_str("(battery: (40, 10), attack: (1., 0.5))").unwrap();
+        assert_eq!(balance.max_battery(2), 60);
+        assert_eq!(balance.attack(2), 2.);
+        assert_eq!(balance.price, PRICE);
+
+        let mut save_data = SaveData {
+            money: 100,
+            ..default()
+        };
+        let cheap = Balance {
+            price: [1; 11],
+            ..balance
+        };
+        assert_eq!(
+            save_data.price(Upgrade::Range, &cheap),
+            Some(1)
+        );
+        assert!(save_data.buy(Upgrade::Range, &cheap));
+        assert_eq!(save_data.money, 99);
+    }
+
+    #[test]
+    fn restart_resets_the_run() {
+        let mut game = TestApp::new();
+        game.start_level();
+        {
+            let mut save_data = game.save_data_mut();
+            save_data.level = 3;
+            save_data.battery = 1;
+            save_data.battery_level = 2;
+            save_data.fire = 2;
+            save_data.fire_uses = 0;
+            save_data.undos_left = 0;
+            save_data.money = 40;
+        }
+
+        game.app.world_mut().send_event(RestartEvent);
+        game.update();
+
+        let save_data = game.save_data();
+        assert_eq!(save_data.level, 0);
+        assert_eq!(
+            save_data.battery,
+            Balance::default().max_battery(2)
+        );
+        assert_eq!(save_data.fire_uses, 2);
+        assert_eq!(save_data.undos_left, UNDOS_PER_RUN);
+        assert_eq!(save_data.money, 40);
+        assert_eq!(
+            game.state::<GameState>(),
+            Some(&GameState::Play)
+        );
+    }
+
+    #[test]
+    fn reload_plays_the_new_save_data() {
+        let mut game = TestApp::new();
+        game.start_level();
+        game.save_data_mut().level = 2;
+        game.save_data_mut().battery_level = 3;
+
+        game.set_state(GameState::Reload);
+        game.run_until("the level to start", |world| {
+            world
+                .get_resource::<State<PlayState>>()
+                .is_some_and(|state| state.get() == &PlayState::Play)
+        });
+        assert_eq!(
+            game.state::<GameState>(),
+            Some(&GameState::Play)
+        );
+        assert_eq!(game.save_data().level, 0);
+        assert_eq!(
+            game.save_data().battery,
+            Balance::default().max_battery(3)
+        );
+        assert!(game.save_data().last_played > 0);
+    }
+
+    fn config(game: &TestApp) -> AppConfig {
+        game.app.world().resource::<AppConfig>().clone()
+    }
+
+    /// Writes the file of a slot that isn't being played
+    fn write_slot(config: &AppConfig, slot: usize, money: u32) {
+        let data = SaveData { money, ..default() };
+        std::fs::create_dir_all(config.data_dir).unwrap();
+        std::fs::write(
+            slot_path(config, slot, "save", "toml"),
+            toml::to_string(&data).unwrap(),
+        )
+        .unwrap();
+    }
+
+    fn send_slot_event(game: &mut TestApp, event: SlotEvent) {
+        game.app.world_mut().send_event(event);
+        game.update();
+    }
+
+    fn slot_game(game: &TestApp, slot: usize) -> Vec<Element> {
+        let achievements = game
+            .app
+            .world()
+            .resource::<Persistent<AchievementProgress>>();
+        achievements.game(slot).elements
+    }
+
+    #[test]
+    fn peeking_a_slot_leaves_its_files_alone() {
+        let game = TestApp::new();
+        let config = config(&game);
+        let files = || std::fs::read_dir(config.data_dir).unwrap().count();
+
+        let before = files();
+        assert!(peek_slot(&config, 1).is_new());
+        assert_eq!(files(), before);
+        assert!(!slot_path(&config, 1, "save", "toml").exists());
+
+        write_slot(&config, 2, 30);
+        let path = slot_path(&config, 2, "save", "toml");
+        let file = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(peek_slot(&config, 2).money, 30);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            file
+        );
+        assert_eq!(files(), before + 1);
+    }
+
+    #[test]
+    fn switching_slots_plays_their_save_data() {
+        let mut game = TestApp::new();
+        let config = config(&game);
+        write_slot(&config, 1, 70);
+
+        // The active slot is only resumed
+        send_slot_event(&mut game, SlotEvent::Switch(0));
+        game.update();
+        assert_eq!(
+            game.state::<PlayState>(),
+            Some(&PlayState::Play)
+        );
+
+        send_slot_event(&mut game, SlotEvent::Switch(1));
+        game.run_until("the slot to reload", |world| {
+            world.resource::<State<GameState>>().get() == &GameState::Reload
+        });
+        game.run_until("the level to start", |world| {
+            world.resource::<State<GameState>>().get() == &GameState::Play
+        });
+        let options = game.app.world().resource::<Persistent<GameOptions>>();
+        assert_eq!(options.active_slot, 1);
+        assert_eq!(game.save_data().money, 70);
+        assert_eq!(game.save_data().level, 0);
+    }
+
+    #[test]
+    fn copying_a_slot_fills_the_first_empty_one() {
+        let mut game = TestApp::new();
+        let config = config(&game);
+        write_slot(&config, 1, 10);
+        let _ = game.save_data_mut().update(|data| data.money = 40);
+        let _ = game
+            .app
+            .world_mut()
+            .resource_mut::<Persistent<AchievementProgress>>()
+            .update(|progress| {
+                progress.set_game(0, GameProgress {
+                    elements: vec![Element::Fire],
+                })
+            });
+
+        send_slot_event(&mut game, SlotEvent::Copy(0));
+        assert_eq!(peek_slot(&config, 1).money, 10);
+        assert_eq!(peek_slot(&config, 2).money, 40);
+        assert_eq!(slot_game(&game, 2), [Element::Fire]);
+    }
+
+    #[test]
+    fn copying_without_an_empty_slot_does_nothing() {
+        let mut game = TestApp::new();
+        let config = config(&game);
+        write_slot(&config, 1, 10);
+        write_slot(&config, 2, 20);
+        let _ = game.save_data_mut().update(|data| data.money = 40);
+
+        send_slot_event(&mut game, SlotEvent::Copy(1));
+        assert_eq!(game.save_data().money, 40);
+        assert_eq!(peek_slot(&config, 1).money, 10);
+        assert_eq!(peek_slot(&config, 2).money, 20);
+    }
+
+    #[test]
+    fn deleting_a_slot_resets_it() {
+        let mut game = TestApp::new();
+        let config = config(&game);
+        write_slot(&config, 1, 10);
+        std::fs::write(suspend::suspend_path(&config, 1), "").unwrap();
+        let _ = game
+            .app
+            .world_mut()
+            .resource_mut::<Persistent<AchievementProgress>>()
+            .update(|progress| {
+                progress.set_game(1, GameProgress {
+                    elements: vec![Element::Water],
+                })
+            });
+        let _ = game.save_data_mut().update(|data| data.money = 40);
+
+        send_slot_event(&mut game, SlotEvent::Delete(1));
+        assert!(peek_slot(&config, 1).is_new());
+        assert!(!suspend::suspend_path(&config, 1).exists());
+        assert!(slot_game(&game, 1).is_empty());
+
+        // The slot being played is kept
+        send_slot_event(&mut game, SlotEvent::Delete(0));
+        assert_eq!(game.save_data().money, 40);
+        assert_eq!(peek_slot(&config, 0).money, 40);
+    }
+}
@@ -0,0 +1,294 @@
+//! Run history submodule
+//! Every time the game is won, the run is added to a history that is kept in
+//! its own file, shared by all the save slots, so the best scores are not lost
+//! when the save data starts over
+
+use std::cmp::Reverse;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{now, Persistent, SaveData, Upgrade};
+use crate::{replay::is_playing, AppConfig, GameState, PlayState};
+
+// ······
+// Plugin
+// ······
+
+/// Run history
+/// Records finished runs and their scores
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Startup),
+            init_history,
+        )
+        .add_systems(
+            OnEnter(PlayState::GameWon),
+            record_run.run_if(not(is_playing)),
+        );
+    }
+}
+
+/// A run that got to the artifact
+/// Only won runs are recorded, since dying doesn't end a run, it costs money
+/// and a death and the run goes on from the shop
+/// A run that is abandoned for a new game never finished, so it has no score
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// Seconds since the unix epoch when the run was won, 0 if the clock isn't
+    /// available
+    pub date: u64,
+    pub score: i32,
+    pub kills: u32,
+    pub levels_completed: u32,
+    pub deaths: u32,
+    /// One entry for each level bought of each upgrade
+    pub upgrades: Vec<Upgrade>,
+    #[serde(with = "super::seed_format")]
+    pub seed: u64,
+}
+
+/// Order of the records table
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RecordSort {
+    #[default]
+    Score,
+    Recent,
+    Kills,
+    Levels,
+}
+
+impl RecordSort {
+    pub const ALL: [Self; 4] = [Self::Score, Self::Recent, Self::Kills, Self::Levels];
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Score => Self::Recent,
+            Self::Recent => Self::Kills,
+            Self::Kills => Self::Levels,
+            Self::Levels => Self::Score,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Score => "score",
+            Self::Recent => "most recent",
+            Self::Kills => "kills",
+            Self::Levels => "levels",
+        }
+    }
+
+    /// Best runs first, ties go to the highest score and then the newest run
+    pub fn sort(self, runs: &mut [RunRecord]) {
+        runs.sort_by_key(|run| self.key(run));
+    }
+
+    fn key(self, run: &RunRecord) -> Reverse<(i64, i32, u64)> {
+        let key = match self {
+            Self::Score => run.score as i64,
+            Self::Recent => run.date as i64,
+            Self::Kills => run.kills as i64,
+            Self::Levels => run.levels_completed as i64,
+        };
+        Reverse((key, run.score, run.date))
+    }
+}
+
+// ·········
+// Resources
+// ·········
+
+/// Runs that were won, in the order they finished
+/// Only the best runs of each order are kept, see `RunHistory::add`
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct RunHistory {
+    pub runs: Vec<RunRecord>,
+}
+
+/// Runs kept for each order of the records table
+pub const MAX_RECORDS: usize = 8;
+
+impl RunHistory {
+    /// Adds a run and forgets the ones that aren't in the top `MAX_RECORDS` of
+    /// any order, so the table looks the same but the file doesn't keep growing
+    pub fn add(&mut self, record: RunRecord) {
+        self.runs.push(record);
+
+        let mut keep = vec![false; self.runs.len()];
+        for sort in RecordSort::ALL {
+            let mut order: Vec<_> = (0..self.runs.len()).collect();
+            order.sort_by_key(|&i| sort.key(&self.runs[i]));
+            for &i in order.iter().take(MAX_RECORDS) {
+                keep[i] = true;
+            }
+        }
+
+        let mut keep = keep.into_iter();
+        self.runs.retain(|_| keep.next().unwrap_or(false));
+    }
+}
+
+// ·······
+// Systems
+// ·······
+
+#[cfg(feature = "persist")]
+fn init_history(mut cmd: Commands, config: Res<AppConfig>) {
+    let path = std::path::Path::new(config.data_dir);
+    let history = Persistent::<RunHistory>::builder()
+        .name("run history")
+        .format(bevy_persistent::StorageFormat::Toml)
+        .path(path.join("history.toml"))
+        .default(RunHistory::default())
+        .revertible(true)
+        .revert_to_default_on_deserialization_errors(true)
+        .build()
+        .expect("failed to initialize the run history");
+    cmd.insert_resource(history);
+}
+
+#[cfg(not(feature = "persist"))]
+fn init_history(mut cmd: Commands, _config: Res<AppConfig>) {
+    cmd.insert_resource(Persistent(RunHistory::default()));
+}
+
+/// Adds the run to the history before the save data starts over
+fn record_run(save_data: Res<Persistent<SaveData>>, mut history: ResMut<Persistent<RunHistory>>) {
+    let record = RunRecord::new(&save_data);
+    info!(
+        "run won with a score of {}",
+        record.score
+    );
+    let _ = history.update(|history| history.add(record.clone()));
+}
+
+// ·······
+// Helpers
+// ·······
+
+/// Score of a run that got to the artifact
+/// Each animal killed is worth 100 points for every level completed plus one,
+/// so killing late in the run is worth more, and each death takes 200 points
+/// It can be negative
+pub fn score(save_data: &SaveData) -> i32 {
+    (save_data.levels_completed as i32 + 1) * save_data.enemies_killed as i32 * 100
+        - save_data.deaths as i32 * 200
+}
+
+impl RunRecord {
+    pub fn new(save_data: &SaveData) -> Self {
+        Self {
+            date: now(),
+            score: score(save_data),
+            kills: save_data.enemies_killed,
+            levels_completed: save_data.levels_completed,
+            deaths: save_data.deaths,
+            upgrades: save_data.upgrades_bought().collect(),
+            seed: save_data.seed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Balance;
+
+    fn record(score: i32, date: u64, kills: u32) -> RunRecord {
+        RunRecord {
+            date,
+            score,
+            kills,
+            levels_completed: 0,
+            deaths: 0,
+            upgrades: Vec::new(),
+            seed: 0,
+        }
+    }
+
+    #[test]
+    fn records_are_sorted_best_first() {
+        let mut runs = vec![record(100, 3, 5), record(900, 1, 5), record(500, 2, 9)];
+        let scores = |runs: &[RunRecord]| runs.iter().map(|run| run.score).collect::<Vec<_>>();
+
+        RecordSort::Score.sort(&mut runs);
+        assert_eq!(scores(&runs), [900, 500, 100]);
+        RecordSort::Recent.sort(&mut runs);
+        assert_eq!(scores(&runs), [100, 500, 900]);
+        // Ties in kills go to the best score
+        RecordSort::Kills.sort(&mut runs);
+        assert_eq!(scores(&runs), [500, 900, 100]);
+    }
+
+    #[test]
+    fn only_the_best_runs_are_kept() {
+        let mut history = RunHistory::default();
+        // Old runs with more and more points
+        for i in 0..MAX_RECORDS as u64 {
+            history.add(record(100 * (i as i32 + 1), i, 0));
+        }
+        // A run with the most kills but few points
+        history.add(record(1, 100, 50));
+        // Newer runs with less points than any before
+        for i in 0..MAX_RECORDS as u64 {
+            history.add(record(0, 200 + i, 0));
+        }
+
+        let runs = &history.runs;
+        assert!(runs.len() < 3 * MAX_RECORDS);
+        // The best scores, the most kills and the newest runs stay
+        assert!(runs.iter().any(|run| run.score == 100 * MAX_RECORDS as i32));
+        assert!(runs.iter().any(|run| run.kills == 50));
+        assert!(runs
+            .iter()
+            .any(|run| run.date == 200 + MAX_RECORDS as u64 - 1));
+        // They are still in the order they finished
+        assert!(runs.windows(2).all(|pair| pair[0].date < pair[1].date));
+
+        let mut best = runs.clone();
+        RecordSort::Score.sort(&mut best);
+        assert_eq!(best[0].score, 100 * MAX_RECORDS as i32);
+    }
+
+    #[test]
+    fn history_is_saved_with_any_seed() {
+        let history = RunHistory {
+            runs: vec![RunRecord {
+                seed: u64::MAX,
+                ..record(100, 1, 2)
+            }],
+        };
+
+        let file = toml::to_string(&history).unwrap();
+        let loaded: RunHistory = toml::from_str(&file).unwrap();
+        assert_eq!(loaded.runs[0].seed, u64::MAX);
+        assert_eq!(loaded.runs[0].score, 100);
+    }
+
+    #[test]
+    fn records_count_the_upgrades_bought() {
+        let mut save_data = SaveData {
+            money: 1000,
+            enemies_killed: 3,
+            levels_completed: 4,
+            deaths: 1,
+            ..default()
+        };
+        let balance = Balance::default();
+        save_data.buy(Upgrade::Range, &balance);
+        save_data.buy(Upgrade::Fire, &balance);
+        save_data.buy(Upgrade::Fire, &balance);
+
+        let record = RunRecord::new(&save_data);
+        assert_eq!(record.score, 5 * 3 * 100 - 200);
+        assert_eq!(record.upgrades, [
+            Upgrade::Range,
+            Upgrade::Fire,
+            Upgrade::Fire
+        ]);
+    }
+}
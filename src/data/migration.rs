@@ -0,0 +1,184 @@
+//! Save migration submodule
+//! Save files keep the version of `SaveData` they were written with. Before
+//! loading them, older files are upgraded one version at a time until they
+//! match the current struct, so changing it doesn't reset the progress
+//! The original file is copied next to the save before it is modified
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use toml::{Table, Value};
+
+use super::{SaveData, UNDOS_PER_RUN};
+
+/// Version of the current `SaveData`
+/// CHANGE: Increase it and add a migration every time the save data changes
+pub const SAVE_VERSION: u32 = 2;
+
+/// Upgrades from each version to the next, in order
+const MIGRATIONS: [fn(&mut Table); SAVE_VERSION as usize] = [v0_to_v1, v1_to_v2];
+
+// ·······
+// Helpers
+// ·······
+
+/// Version a save was written with, saves from before versioning are 0
+pub fn version(save: &Table) -> u32 {
+    save.get("version")
+        .and_then(Value::as_integer)
+        .map_or(0, |version| version as u32)
+}
+
+/// Upgrades a save to the current version
+pub fn migrate(save: &mut Table) {
+    let from = version(save) as usize;
+    for migration in MIGRATIONS.iter().skip(from) {
+        migration(save);
+    }
+    if from < MIGRATIONS.len() {
+        save.insert(
+            "version".into(),
+            Value::Integer(SAVE_VERSION.into()),
+        );
+    }
+}
+
+/// Upgrades the save file in place if it is older than the current version
+/// Saves that don't match `SaveData` are backed up too, since they are going
+/// to be replaced with the default one
+pub fn migrate_file(path: &Path) -> io::Result<()> {
+    let file = match std::fs::read_to_string(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+
+    let Ok(mut save) = file.parse::<Table>() else {
+        warn!("save data is not valid toml, it will be reset");
+        return backup(path, "invalid");
+    };
+    let from = version(&save);
+    if from > SAVE_VERSION {
+        warn!(
+            "save data is from a newer version ({}), it may not load",
+            from
+        );
+        return Ok(());
+    }
+
+    if from < SAVE_VERSION {
+        backup(path, &format!("v{}", from))?;
+        migrate(&mut save);
+        std::fs::write(
+            path,
+            toml::to_string(&save).map_err(io::Error::other)?,
+        )?;
+        info!(
+            "save data migrated from version {} to {}",
+            from, SAVE_VERSION
+        );
+    }
+
+    if let Err(err) = Value::Table(save).try_into::<SaveData>() {
+        warn!(
+            "save data can't be loaded, it will be reset: {}",
+            err
+        );
+        return backup(path, "invalid");
+    }
+    Ok(())
+}
+
+/// Copies a file to `<name>.<suffix>.bak`
+fn backup(path: &Path, suffix: &str) -> io::Result<()> {
+    std::fs::copy(path, backup_path(path, suffix)).map(|_| ())
+}
+
+fn backup_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.bak", suffix));
+    path.with_file_name(name)
+}
+
+// ··········
+// Migrations
+// ··········
+
+/// Runs started keeping their seed and how many turns can be undone
+fn v0_to_v1(save: &mut Table) {
+    save.entry("seed").or_insert(Value::Integer(0));
+    save.entry("undos_left")
+        .or_insert(Value::Integer(UNDOS_PER_RUN.into()));
+}
+
+/// Saves were split in slots, showing when they were last played
+fn v1_to_v2(save: &mut Table) {
+    save.entry("last_played").or_insert(Value::Integer(0));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAVES: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/saves"
+    );
+
+    fn load(name: &str) -> Table {
+        let path = Path::new(SAVES).join(name);
+        std::fs::read_to_string(path).unwrap().parse().unwrap()
+    }
+
+    #[test]
+    fn old_saves_keep_loading() {
+        let mut count = 0;
+        for entry in std::fs::read_dir(SAVES).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            let mut save = load(&name);
+            migrate(&mut save);
+            assert_eq!(version(&save), SAVE_VERSION, "{}", name);
+            if let Err(err) = Value::Table(save).try_into::<SaveData>() {
+                panic!("{} doesn't load: {}", name, err);
+            }
+            count += 1;
+        }
+        assert!(count > 0);
+    }
+
+    #[test]
+    fn migration_keeps_progress() {
+        let mut save = load("v0-jam.toml");
+        migrate(&mut save);
+        let data: SaveData = Value::Table(save).try_into().unwrap();
+        assert_eq!(data.version, SAVE_VERSION);
+        assert_eq!(data.undos_left, UNDOS_PER_RUN);
+        assert_eq!(data.money, 35);
+        assert_eq!(data.battery_level, 2);
+        assert_eq!(data.levels_completed, 7);
+    }
+
+    #[test]
+    fn migrating_a_file_writes_a_backup() {
+        let dir = std::env::temp_dir().join(format!(
+            "kenney-jam-migration-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("save.toml");
+        let old = std::fs::read_to_string(Path::new(SAVES).join("v0-jam.toml")).unwrap();
+        std::fs::write(&path, &old).unwrap();
+
+        migrate_file(&path).unwrap();
+        let backup = std::fs::read_to_string(dir.join("save.toml.v0.bak")).unwrap();
+        let new: Table = std::fs::read_to_string(&path).unwrap().parse().unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(backup, old);
+        assert_eq!(version(&new), SAVE_VERSION);
+    }
+}
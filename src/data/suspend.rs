@@ -0,0 +1,321 @@
+//! Suspend submodule
+//! Exiting in the middle of a level writes all of it to a suspend file, and the
+//! next time the slot is played the level continues from there instead of
+//! generating it again. The file is removed as soon as it is loaded, and when
+//! the run ends, so it can't be used to go back and retry a level
+//! It needs files, so it is not available on the web
+
+use std::{collections::HashMap, io, path::PathBuf};
+
+use bevy::{app::AppExit, prelude::*};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::{slot_path, GameOptions, Persistent, SaveData};
+use crate::{
+    assets::SpriteAssets,
+    enemy::{EnemySnapshot, SnapshotQuery},
+    player::{self, Player},
+    replay::{self, Replayer},
+    tilemap::{
+        self,
+        fov::{remembered, Tint},
+        tile_to_pos, GameRng, LevelLayout, Seen, Tilemap,
+    },
+    turn::Scheduler,
+    AppConfig, GameState, PlayState,
+};
+
+// ······
+// Plugin
+// ······
+
+/// Suspend
+/// Saves the level being played when the game exits and restores it later
+pub struct SuspendPlugin;
+
+impl Plugin for SuspendPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Loading),
+            load_suspend,
+        )
+        .add_systems(OnEnter(GameState::Reload), load_suspend)
+        .add_systems(
+            OnEnter(GameState::Play),
+            (
+                resume_layout
+                    .before(super::touch_slot)
+                    .after(tilemap::init)
+                    .before(tilemap::spawn_level),
+                resume_level
+                    .after(player::init)
+                    .after(replay::start_recording),
+            )
+                .run_if(resource_exists::<Suspended>),
+        )
+        .add_systems(
+            OnEnter(PlayState::GameOver),
+            remove_suspend,
+        )
+        .add_systems(
+            OnEnter(PlayState::GameWon),
+            remove_suspend,
+        )
+        .add_systems(
+            Last,
+            suspend.run_if(
+                on_event::<AppExit>()
+                    .and_then(in_state(PlayState::Menu).or_else(in_state(PlayState::Play))),
+            ),
+        );
+    }
+}
+
+/// Everything in a level that isn't generated again from its seed
+#[derive(Serialize, Deserialize)]
+pub struct Suspend {
+    /// Save data as it was in the level, it may be ahead of the slot file
+    pub save_data: SaveData,
+    /// Layout of the level, its enemies are the ones it started with
+    pub layout: LevelLayout,
+    pub player: IVec2,
+    /// Player entity when the level was suspended, to find it in the turn order
+    pub player_entity: Entity,
+    pub enemies: Vec<EnemySnapshot>,
+    pub scheduler: Scheduler,
+    /// Seeds the game rng when the level is resumed
+    pub rng_seed: u64,
+    /// Tiles the player has already seen
+    pub seen: Vec<IVec2>,
+}
+
+// ·········
+// Resources
+// ·········
+
+/// Suspended level that will be resumed instead of creating a new one
+#[derive(Resource)]
+pub struct Suspended(pub Suspend);
+
+// ·······
+// Systems
+// ·······
+
+/// Reads the suspend file of the active slot, and removes it
+fn load_suspend(
+    mut cmd: Commands,
+    config: Res<AppConfig>,
+    options: Res<Persistent<GameOptions>>,
+    replayer: Res<Replayer>,
+) {
+    if matches!(*replayer, Replayer::Playing { .. }) {
+        return;
+    }
+    let path = suspend_path(&config, options.active_slot);
+    let file = match std::fs::read_to_string(&path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return,
+        Err(err) => {
+            return error!(
+                "failed to read the suspended level: {}",
+                err
+            )
+        },
+    };
+    remove_file(&config, options.active_slot);
+
+    match ron::de::from_str::<Suspend>(&file) {
+        Ok(suspend) => cmd.insert_resource(Suspended(suspend)),
+        Err(err) => warn!(
+            "the suspended level can't be loaded, a new one will be created: {}",
+            err
+        ),
+    }
+}
+
+/// Replaces the level that was just created with the suspended one, before it
+/// is spawned
+fn resume_layout(
+    mut cmd: Commands,
+    suspended: Res<Suspended>,
+    mut save_data: ResMut<Persistent<SaveData>>,
+) {
+    let suspend = &suspended.0;
+    let _ = save_data.update(|data| *data = suspend.save_data.clone());
+    let mut layout = suspend.layout.clone();
+    layout.enemies.clear();
+    cmd.insert_resource(layout);
+    cmd.insert_resource(GameRng(StdRng::seed_from_u64(
+        suspend.rng_seed,
+    )));
+}
+
+/// Moves the player, spawns the enemies and restores the turn order and
+/// explored tiles of the suspended level
+fn resume_level(
+    mut cmd: Commands,
+    suspended: Res<Suspended>,
+    sprite_assets: Res<SpriteAssets>,
+    mut player: Query<(Entity, &mut Player, &mut Transform)>,
+    mut tiles: Query<(
+        &mut Seen,
+        &mut Visibility,
+        &mut Sprite,
+        &Tint,
+    )>,
+    mut tilemap: ResMut<Tilemap>,
+    mut scheduler: ResMut<Scheduler>,
+    mut replayer: ResMut<Replayer>,
+) {
+    let suspend = &suspended.0;
+    cmd.remove_resource::<Suspended>();
+    let Ok((entity, mut player, mut trans)) = player.get_single_mut() else {
+        return;
+    };
+
+    tilemap.set_occupant(player.pos, None);
+    player.pos = suspend.player;
+    trans.translation = tile_to_pos(player.pos).extend(trans.translation.z);
+    tilemap.set_occupant(player.pos, Some(entity));
+
+    let mut respawned = HashMap::from([(suspend.player_entity, entity)]);
+    for saved in suspend.enemies.iter().cloned() {
+        let old = saved.entity;
+        respawned.insert(
+            old,
+            saved.respawn(&mut cmd, &sprite_assets, &mut tilemap),
+        );
+    }
+    *scheduler = suspend.scheduler.clone();
+    scheduler.replace(&respawned);
+
+    for pos in &suspend.seen {
+        let Some(cell) = tilemap.get(*pos) else { continue };
+        let Ok((mut seen, mut visibility, mut sprite, tint)) = tiles.get_mut(cell.entity) else {
+            continue;
+        };
+        *seen = Seen::Remembered;
+        *visibility = Visibility::Inherited;
+        sprite.color = remembered(tint.0);
+    }
+
+    // The level didn't start as its seed creates it, so it can't be replayed
+    *replayer = Replayer::Off;
+    info!(
+        "resumed level {}",
+        suspend.save_data.level
+    );
+}
+
+/// Writes the level being played to the suspend file of the active slot
+fn suspend(
+    player: Query<(Entity, &Player)>,
+    enemies: SnapshotQuery,
+    tiles: Query<&Seen>,
+    layout: Res<LevelLayout>,
+    tilemap: Res<Tilemap>,
+    scheduler: Res<Scheduler>,
+    mut rng: ResMut<GameRng>,
+    save_data: Res<Persistent<SaveData>>,
+    options: Res<Persistent<GameOptions>>,
+    config: Res<AppConfig>,
+) {
+    let Ok((player_entity, player)) = player.get_single() else { return };
+    if save_data.battery == 0 {
+        return;
+    }
+
+    let seen = tilemap
+        .iter()
+        .filter(|(_, cell)| {
+            tiles
+                .get(cell.entity)
+                .is_ok_and(|seen| *seen != Seen::Unseen)
+        })
+        .map(|(pos, _)| pos)
+        .collect();
+    let suspend = Suspend {
+        save_data: SaveData::clone(&save_data),
+        layout: layout.clone(),
+        player: player.pos,
+        player_entity,
+        enemies: EnemySnapshot::take_all(&enemies),
+        scheduler: scheduler.clone(),
+        rng_seed: rng.0.gen(),
+        seen,
+    };
+
+    let path = suspend_path(&config, options.active_slot);
+    let written = ron::ser::to_string(&suspend)
+        .map_err(io::Error::other)
+        .and_then(|file| {
+            std::fs::create_dir_all(config.data_dir)?;
+            std::fs::write(&path, file)
+        });
+    match written {
+        Ok(()) => info!("level suspended to {}", path.display()),
+        Err(err) => error!("failed to suspend the level: {}", err),
+    }
+}
+
+/// The run is over, so there is nothing to resume
+fn remove_suspend(config: Res<AppConfig>, options: Res<Persistent<GameOptions>>) {
+    remove_file(&config, options.active_slot);
+}
+
+// ·······
+// Helpers
+// ·······
+
+/// Suspend file of a slot
+pub fn suspend_path(config: &AppConfig, slot: usize) -> PathBuf {
+    slot_path(config, slot, "suspend", "ron")
+}
+
+/// Removes the suspend file of a slot if it has one
+pub fn remove_file(config: &AppConfig, slot: usize) {
+    match std::fs::remove_file(suspend_path(config, slot)) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => {
+            error!(
+                "failed to remove the suspended level: {}",
+                err
+            )
+        },
+        _ => {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestApp;
+
+    #[test]
+    fn exiting_suspends_the_level() {
+        let mut game = TestApp::new();
+        game.start_level();
+        let dir = game.free_direction();
+        game.step(dir);
+        game.save_data_mut().money = 42;
+        let pos = game.player_pos();
+        let before = game.enemies();
+
+        game.set_state(PlayState::Menu);
+        game.app.world_mut().send_event(AppExit::Success);
+        game.update();
+        let config = game.app.world().resource::<AppConfig>().clone();
+        assert!(suspend_path(&config, 0).exists());
+
+        let mut game = game.relaunch();
+        assert!(!suspend_path(&config, 0).exists());
+        assert_eq!(game.player_pos(), pos);
+        assert_eq!(game.enemies(), before);
+        assert_eq!(game.save_data().money, 42);
+
+        game.start_level();
+        let dir = game.free_direction();
+        game.step(dir);
+        assert_ne!(game.player_pos(), pos);
+    }
+}
@@ -5,16 +5,36 @@ use bevy::prelude::*;
 pub use bevy_persistent::prelude::Persistent;
 use rand::Rng;
 
+use self::{
+    behaviour::{decide, AiContext, Intent, ALERT_TURNS},
+    chart::charges,
+    status::{apply_status, ROOTED_FIRE_BONUS},
+};
+pub use self::{
+    behaviour::{AiState, Behaviour},
+    chart::ElementChart,
+    snapshot::{EnemySnapshot, SnapshotQuery},
+    status::{Burning, Rooted, Soaked},
+};
 use crate::{
-    assets::{CoreAssets, SoundAssets, ATLAS_SIZE},
-    data::{attack, max_battery, SaveData},
-    misc::{dir_to_vec, Direction, MoveTo, MIN_TURN_TIMER},
+    assets::{sync_asset_resource, CoreAssets, RonAssetLoader, SoundAssets, SpriteCatalogue},
+    data::{Balance, SaveData},
+    misc::{vec_to_dir, MoveTo},
     player::Player,
-    tilemap::{tile_to_pos, Tile, Tilemap},
+    tilemap::{pathfinding::PlayerDistances, tile_to_pos, GameRng, LevelLayout, Tile, Tilemap},
+    turn::{Scheduler, NORMAL_SPEED},
     PlaySet, PlayState, TurnState,
 };
 
-const WEIGHTS: [[u32; 7]; 12] = [
+pub mod behaviour;
+pub mod chart;
+pub mod snapshot;
+pub mod status;
+
+/// Chance out of 100 of each enemy type for each level, unless `Balance`
+/// changes it
+/// Chicken, cat, dog, young and old, man, money and battery
+pub const WEIGHTS: [[u32; 7]; 12] = [
     [80, 10, 00, 00, 00, 10, 0],
     [65, 20, 5, 00, 00, 10, 0],
     [35, 30, 25, 00, 00, 20, 0],
@@ -37,8 +57,20 @@ pub struct EnemyPlugin;
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<DamageEvent>()
-            .add_systems(OnEnter(TurnState::Enemy), enemy_turn)
+        app.init_asset::<ElementChart>()
+            .init_asset_loader::<RonAssetLoader<ElementChart>>()
+            .init_resource::<ElementChart>()
+            .add_event::<DamageEvent>()
+            .add_event::<PlayerHitEvent>()
+            .add_event::<KillEvent>()
+            .add_systems(
+                Update,
+                sync_asset_resource::<ElementChart>,
+            )
+            .add_systems(
+                OnEnter(TurnState::Enemy),
+                status::tick_status,
+            )
             .add_systems(
                 Update,
                 update_enemies.run_if(in_state(TurnState::Enemy)),
@@ -47,7 +79,11 @@ impl Plugin for EnemyPlugin {
                 Update,
                 (
                     on_damage.in_set(PlaySet::Events),
-                    (damage_text, enemy_flash).in_set(PlaySet::Animation),
+                    (
+                        damage_text,
+                        (enemy_flash, status::status_tint).chain(),
+                    )
+                        .in_set(PlaySet::Animation),
                 ),
             );
     }
@@ -57,6 +93,7 @@ impl Plugin for EnemyPlugin {
 // Components
 // ··········
 
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum EnemyType {
     Chicken,
     Cat,
@@ -68,7 +105,7 @@ pub enum EnemyType {
     EndGame, // This is not an enemy, its a jewel
 }
 
-#[derive(serde::Deserialize, serde::Serialize, PartialEq)]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Element {
     Basic,
     Fire,
@@ -93,7 +130,7 @@ impl Element {
     }
 }
 
-#[derive(Component)]
+#[derive(Component, Clone, serde::Deserialize, serde::Serialize)]
 pub struct Enemy {
     pub pos: IVec2,
     pub health: f32,
@@ -102,10 +139,7 @@ pub struct Enemy {
 }
 
 #[derive(Component)]
-struct EnemyTurn(Timer);
-
-#[derive(Component)]
-struct EnemyFlash(Timer);
+pub(crate) struct EnemyFlash(Timer);
 
 #[derive(Component)]
 struct DamageText(Timer, Vec2);
@@ -114,8 +148,48 @@ struct DamageText(Timer, Vec2);
 // Events
 // ······
 
+/// The player attacked an enemy
+#[derive(Event)]
+pub struct DamageEvent {
+    pub enemy: Entity,
+    pub elem: Element,
+    /// Scales the attack power
+    pub power: f32,
+    /// The charge was already spent by an area attack
+    pub paid: bool,
+    /// Direction the enemy is pushed if it survives
+    pub push: Option<IVec2>,
+}
+
+impl DamageEvent {
+    /// Hit from bumping into an enemy
+    pub fn bump(enemy: Entity, elem: Element) -> Self {
+        Self {
+            enemy,
+            elem,
+            power: 1.,
+            paid: false,
+            push: None,
+        }
+    }
+}
+
+/// An enemy attacked the player, draining its battery
 #[derive(Event)]
-pub struct DamageEvent(pub Entity);
+pub struct PlayerHitEvent {
+    pub enemy: Entity,
+    pub damage: u32,
+    pub elem: Element,
+}
+
+/// An enemy ran out of health, pickups included
+#[derive(Event)]
+pub struct KillEvent {
+    pub typ: EnemyType,
+    pub elem: Element,
+    /// Money it dropped
+    pub money: u32,
+}
 
 // ·······
 // Systems
@@ -123,192 +197,109 @@ pub struct DamageEvent(pub Entity);
 
 fn on_damage(
     mut cmd: Commands,
-    mut enemies: Query<&mut Enemy>,
+    mut enemies: Query<(
+        &mut Enemy,
+        Option<&Burning>,
+        Option<&Soaked>,
+        Option<&Rooted>,
+    )>,
     sound_assets: Res<SoundAssets>,
     mut damage_reader: EventReader<DamageEvent>,
     mut save_data: ResMut<Persistent<SaveData>>,
     mut next_play_state: ResMut<NextState<PlayState>>,
     assets: Res<CoreAssets>,
+    mut tilemap: ResMut<Tilemap>,
+    mut rng: ResMut<GameRng>,
+    chart: Res<ElementChart>,
+    balance: Res<Balance>,
+    mut kill_writer: EventWriter<KillEvent>,
 ) {
-    for DamageEvent(entity) in damage_reader.read() {
+    let mut killed = Vec::new();
+    for event in damage_reader.read() {
+        let entity = &event.enemy;
         cmd.entity(*entity)
             .try_insert(EnemyFlash(Timer::from_seconds(
                 0.15,
                 TimerMode::Once,
             )));
 
-        if let Ok(mut enemy) = enemies.get_mut(*entity) {
+        if let Ok((mut enemy, burning, soaked, rooted)) = enemies.get_mut(*entity) {
+            // Already killed by another hit this frame
+            if killed.contains(entity) {
+                continue;
+            }
+
             if let EnemyType::EndGame = enemy.typ {
                 next_play_state.set(PlayState::GameWon);
                 return;
             }
 
             if let EnemyType::Battery = enemy.typ {
-                save_data.battery = (save_data.battery + max_battery(save_data.battery_level) / 4)
-                    .clamp(0, max_battery(save_data.battery_level));
+                let max = balance.max_battery(save_data.battery_level);
+                save_data.battery = (save_data.battery + max / 4).clamp(0, max);
             }
 
-            let value = match enemy.elem {
-                Element::Basic => match save_data.attack_selected {
-                    Element::Basic => attack(save_data.attack_level),
-                    Element::Fire => {
-                        if save_data.fire_uses > 0 {
-                            save_data.fire_uses -= 1;
-                            attack(save_data.attack_level)
-                        } else {
-                            0.
-                        }
-                    },
-                    Element::Water => {
-                        if save_data.water_uses > 0 {
-                            save_data.water_uses -= 1;
-                            attack(save_data.attack_level)
-                        } else {
-                            0.
-                        }
-                    },
-                    Element::Grass => {
-                        if save_data.grass_uses > 0 {
-                            save_data.grass_uses -= 1;
-                            attack(save_data.attack_level)
-                        } else {
-                            0.
-                        }
-                    },
+            let elem = event.elem;
+            let outcome = chart.outcome(
+                elem,
+                enemy.elem,
+                balance.attack(save_data.attack_level) * event.power,
+                match event.paid {
+                    true => None,
+                    false => charges(&mut save_data, elem).map(|uses| *uses),
                 },
-                Element::Fire => match save_data.attack_selected {
-                    Element::Basic => attack(save_data.attack_level),
-                    Element::Fire => {
-                        if save_data.fire_uses > 0 {
-                            save_data.fire_uses -= 1;
-                            attack(save_data.attack_level)
-                        } else {
-                            0.
-                        }
-                    },
-                    Element::Water => {
-                        if save_data.water_uses > 0 {
-                            save_data.water_uses -= 1;
-                            attack(save_data.attack_level) * 1.5
-                        } else {
-                            0.
-                        }
-                    },
-                    Element::Grass => {
-                        if save_data.grass_uses > 0 {
-                            save_data.grass_uses -= 1;
-                            save_data.battery -= attack(save_data.attack_level) as u32;
-                        }
-                        0.
-                    },
-                },
-                Element::Water => match save_data.attack_selected {
-                    Element::Basic => attack(save_data.attack_level),
-                    Element::Fire => {
-                        if save_data.fire_uses > 0 {
-                            save_data.fire_uses -= 1;
-                            save_data.battery -= attack(save_data.attack_level) as u32;
-                        }
-                        0.
-                    },
-                    Element::Water => {
-                        if save_data.water_uses > 0 {
-                            save_data.water_uses -= 1;
-                            attack(save_data.attack_level)
-                        } else {
-                            0.
-                        }
-                    },
-                    Element::Grass => {
-                        if save_data.grass_uses > 0 {
-                            save_data.grass_uses -= 1;
-                            attack(save_data.attack_level) * 1.5
-                        } else {
-                            0.
-                        }
-                    },
-                },
-                Element::Grass => match save_data.attack_selected {
-                    Element::Basic => attack(save_data.attack_level),
-                    Element::Fire => {
-                        if save_data.fire_uses > 0 {
-                            save_data.fire_uses -= 1;
-                            attack(save_data.attack_level) * 1.5
-                        } else {
-                            0.
-                        }
-                    },
-                    Element::Water => {
-                        if save_data.water_uses > 0 {
-                            save_data.water_uses -= 1;
-                            save_data.battery -= attack(save_data.attack_level) as u32;
-                        }
-                        0.
-                    },
-                    Element::Grass => {
-                        if save_data.grass_uses > 0 {
-                            save_data.grass_uses -= 1;
-                            attack(save_data.attack_level)
-                        } else {
-                            0.
-                        }
-                    },
-                },
-            };
+            );
+            if outcome.charge_used {
+                if let Some(uses) = charges(&mut save_data, elem) {
+                    *uses -= 1;
+                }
+            }
+            save_data.battery = save_data.battery.saturating_sub(outcome.backfire);
 
+            let mut value = outcome.damage;
+            if elem == Element::Fire && rooted.is_some() {
+                value *= ROOTED_FIRE_BONUS;
+            }
+            if value > 0. {
+                apply_status(
+                    &mut cmd,
+                    *entity,
+                    elem,
+                    &enemy,
+                    (burning, soaked, rooted),
+                );
+            }
             let value = value.clamp(0., enemy.health);
             enemy.health -= value;
 
-            cmd.spawn((
-                Text2dBundle {
-                    text: Text::from_section(
-                        if value > 0. { format!("{:.1}", value) } else { "X".into() },
-                        TextStyle {
-                            font: assets.font.clone(),
-                            font_size: 10.,
-                            color: enemy_color(&save_data.attack_selected).lighter(0.1),
-                        },
-                    ),
-                    transform: Transform::from_translation(tile_to_pos(enemy.pos).extend(15.)),
-                    ..default()
-                },
-                DamageText(
-                    Timer::from_seconds(0.3, TimerMode::Once),
-                    tile_to_pos(enemy.pos),
-                ),
-            ));
+            spawn_damage_text(
+                &mut cmd,
+                &assets,
+                if value > 0. { format!("{:.1}", value) } else { "X".into() },
+                enemy_color(&elem).lighter(0.1),
+                enemy.pos,
+            );
 
             if enemy.health <= 0. {
-                cmd.entity(*entity).despawn();
-                let mut rng = rand::thread_rng();
-                cmd.spawn(AudioBundle {
-                    source: match enemy.typ {
-                        EnemyType::Chicken => sound_assets.chicken[rng.gen_range(0..2)].clone(),
-                        EnemyType::Cat => sound_assets.cat[rng.gen_range(0..3)].clone(),
-                        EnemyType::Dog => sound_assets.dog[rng.gen_range(0..3)].clone(),
-                        EnemyType::YoungOld | EnemyType::Man => {
-                            sound_assets.man[rng.gen_range(0..2)].clone()
-                        },
-                        EnemyType::EndGame | EnemyType::Money | EnemyType::Battery => {
-                            sound_assets.upgrades[rng.gen_range(0..2)].clone()
-                        },
-                    },
-                    settings: PlaybackSettings::DESPAWN,
-                });
-                save_data.money += match enemy.typ {
-                    EnemyType::Chicken => rng.gen_range(4..6),
-                    EnemyType::Cat => rng.gen_range(8..11),
-                    EnemyType::Dog => rng.gen_range(14..17),
-                    EnemyType::YoungOld => rng.gen_range(18..21),
-                    EnemyType::Man => rng.gen_range(24..27),
-                    EnemyType::Money => {
-                        rng.gen_range((save_data.level + 2)..(save_data.level + 1) * 4)
-                    },
-                    EnemyType::EndGame | EnemyType::Battery => 0,
-                };
-                if !matches!(enemy.typ, EnemyType::Money) {
-                    save_data.enemies_killed += 1;
-                };
+                killed.push(*entity);
+                kill_enemy(
+                    &mut cmd,
+                    *entity,
+                    &enemy,
+                    &sound_assets,
+                    &mut save_data,
+                    &mut tilemap,
+                    &mut rng.0,
+                    &mut kill_writer,
+                );
+            } else if let Some(push) = event.push {
+                push_enemy(
+                    &mut cmd,
+                    *entity,
+                    &mut enemy,
+                    push,
+                    &mut tilemap,
+                );
             }
         }
 
@@ -319,13 +310,6 @@ fn on_damage(
     }
 }
 
-fn enemy_turn(mut cmd: Commands) {
-    cmd.spawn(EnemyTurn(Timer::from_seconds(
-        MIN_TURN_TIMER,
-        TimerMode::Once,
-    )));
-}
-
 fn enemy_flash(
     mut cmd: Commands,
     mut enemies: Query<(
@@ -346,71 +330,125 @@ fn enemy_flash(
     }
 }
 
+/// Lets the other actors act in order until it is the turn of the player
+/// An enemy that is still moving waits for its animation before acting again
 fn update_enemies(
     mut cmd: Commands,
-    mut timer: Query<(Entity, &mut EnemyTurn)>,
-    mut enemies: Query<(Entity, &mut Enemy, Option<&MoveTo>)>,
-    player: Query<&Player>,
-    mut tiles: Query<&mut Tile>,
-    tilemap: Res<Tilemap>,
-    time: Res<Time>,
+    mut enemies: Query<(
+        Entity,
+        &mut Enemy,
+        &Behaviour,
+        &mut AiState,
+        Option<&MoveTo>,
+        Has<Soaked>,
+        Has<Rooted>,
+    )>,
+    player: Query<(Entity, &Player)>,
+    mut scheduler: ResMut<Scheduler>,
+    mut tilemap: ResMut<Tilemap>,
+    layout: Res<LevelLayout>,
+    distances: Res<PlayerDistances>,
+    sound_assets: Res<SoundAssets>,
+    mut rng: ResMut<GameRng>,
     mut next_turn_state: ResMut<NextState<TurnState>>,
+    mut hit_writer: EventWriter<PlayerHitEvent>,
 ) {
-    let Ok((entity, mut timer)) = timer.get_single_mut() else { return };
-    let timer = timer.0.tick(time.delta());
-    if timer.just_finished() {
-        next_turn_state.set(TurnState::Player);
-        cmd.entity(entity).despawn();
-    }
-
-    if timer.fraction() < 0.4 {
+    let Ok((player_entity, player)) = player.get_single() else {
         return;
     };
-
-    let Ok(player) = player.get_single() else {
+    if !scheduler.contains(player_entity) {
+        next_turn_state.set(TurnState::Player);
         return;
-    };
+    }
 
-    let mut rng = rand::thread_rng();
-    for (entity, mut enemy, move_to) in enemies.iter_mut() {
-        match enemy.typ {
-            EnemyType::EndGame | EnemyType::Money | EnemyType::Battery => continue,
-            _ => {},
+    let rng = &mut rng.0;
+    let mut calls = Vec::new();
+    let mut acted = Vec::new();
+    while let Some(next) = scheduler.peek() {
+        if next == player_entity {
+            scheduler.pop();
+            next_turn_state.set(TurnState::Player);
+            break;
+        }
+        let Ok((entity, mut enemy, behaviour, mut state, move_to, soaked, rooted)) =
+            enemies.get_mut(next)
+        else {
+            scheduler.remove(next);
+            continue;
+        };
+        if move_to.is_some() || acted.contains(&entity) {
+            break;
+        }
+        scheduler.pop();
+        acted.push(entity);
+
+        let cost = |pos| tilemap.enemy_cost(pos);
+        let ctx = AiContext {
+            pos: enemy.pos,
+            player: player.pos,
+            distances: &distances,
+            rooms: &layout.rooms,
+            cost: &cost,
         };
 
-        if move_to.is_some() {
-            continue;
+        // Soaked enemies lose their turn
+        let intent = if soaked { Intent::Stay } else { decide(behaviour, &mut state, &ctx, rng) };
+        let pos = match intent {
+            // Rooted enemies can only attack
+            Intent::Step(pos) if rooted && pos != player.pos => enemy.pos,
+            Intent::Step(pos) => pos,
+            Intent::Call => {
+                calls.push((
+                    enemy.pos,
+                    behaviour.call.unwrap_or_default(),
+                ));
+                cmd.spawn(AudioBundle {
+                    source: sound_assets.man[1].clone(),
+                    settings: PlaybackSettings::DESPAWN,
+                });
+                enemy.pos
+            },
+            Intent::Stay => enemy.pos,
         };
-        if rng.gen_bool(0.3) {
+        // Enemies move where they plan their paths through
+        let is_free = tilemap.enemy_cost(pos).is_some()
+            && tilemap.get(pos).is_some_and(|cell| cell.occupant.is_none());
+        if pos == enemy.pos || (pos != player.pos && !is_free) {
             cmd.entity(entity).insert(MoveTo::new(
                 tile_to_pos(enemy.pos),
                 tile_to_pos(enemy.pos),
                 None,
             ));
             continue;
-        };
-
-        let dir: Direction = rng.gen();
-        let pos = enemy.pos + dir_to_vec(&dir, 1.).as_ivec2();
-
-        let Some(tile) = tilemap.get_tile(pos) else { continue };
-        let Some(prev_tile) = tilemap.get_tile(enemy.pos) else { continue };
-        let Ok(mut tile) = tiles.get_mut(tile) else { continue };
-        let Tile::Ground = *tile else { continue };
+        }
 
         cmd.entity(entity).insert(MoveTo::new(
             tile_to_pos(enemy.pos),
             tile_to_pos(pos),
-            if pos == player.pos { Some(dir) } else { None },
+            if pos == player.pos { vec_to_dir(pos - enemy.pos) } else { None },
         ));
 
-        if pos != player.pos {
-            *tile = Tile::Enemy;
+        if pos == player.pos {
+            hit_writer.send(PlayerHitEvent {
+                enemy: entity,
+                damage: hit_damage(enemy.typ, enemy.elem),
+                elem: enemy.elem,
+            });
+        }
 
+        if pos != player.pos {
+            tilemap.move_occupant(enemy.pos, pos);
             enemy.pos = pos;
+        }
+    }
 
-            let Ok(mut prev_tile) = tiles.get_mut(prev_tile) else { continue };
-            *prev_tile = Tile::Ground;
+    // Alert the enemies that heard a call
+    for (call_pos, radius) in calls {
+        for (_, enemy, behaviour, mut state, ..) in enemies.iter_mut() {
+            let dist = (enemy.pos - call_pos).abs().element_sum() as u32;
+            if dist <= radius && !behaviour.is_still() {
+                state.alerted = ALERT_TURNS;
+            }
         }
     }
 }
@@ -443,62 +481,183 @@ fn damage_text(
 // Helpers
 // ·······
 
-pub fn get_enemy(pos: IVec2, level: u32, unique: &mut bool) -> (Enemy, usize) {
-    let mut rng = rand::thread_rng();
-
-    if level == 9 && !*unique {
-        *unique = true;
-        return (
-            Enemy {
-                pos,
-                health: 1.,
-                typ: EnemyType::EndGame,
-                elem: Element::Basic,
-            },
-            45 + rng.gen_range(6..9) * ATLAS_SIZE.0,
-        );
-    }
+/// Creates a random enemy for a level, along with how it behaves and its
+/// sprite index
+pub fn get_enemy(
+    pos: IVec2,
+    level: u32,
+    sprites: &SpriteCatalogue,
+    balance: &Balance,
+    rng: &mut impl Rng,
+) -> (Enemy, Behaviour, usize) {
+    let typ = enemy_type(level, &balance.weights, rng);
+    let elem = match typ {
+        EnemyType::Money | EnemyType::Battery => Element::Basic,
+        _ => enemy_elem(rng),
+    };
+    new_enemy(pos, typ, elem, sprites, rng)
+}
 
-    let typ = enemy_type(level);
-    let (index, health) = match typ {
-        EnemyType::Chicken => (
-            7 * ATLAS_SIZE.0 + 25 + rng.gen_range(0..2),
-            1.,
-        ),
-        EnemyType::Cat => (
-            7 * ATLAS_SIZE.0 + 29 + rng.gen_range(0..2),
-            2.,
-        ),
-        EnemyType::Dog => (7 * ATLAS_SIZE.0 + 31, 3.),
-        EnemyType::YoungOld => (
-            4 * ATLAS_SIZE.0 + 28 + rng.gen_range(0..2),
-            4.,
-        ),
-        EnemyType::Man => (26 + rng.gen_range(0..6), 5.),
-        EnemyType::Money => (10 * ATLAS_SIZE.0 + 33, 0.),
-        EnemyType::Battery => (22 * ATLAS_SIZE.0 + 8, 0.),
-        _ => unreachable!(),
+/// Creates an enemy of a specific type, returning it along with its behaviour
+/// and sprite index. The rng only chooses between sprite variations
+pub fn new_enemy(
+    pos: IVec2,
+    typ: EnemyType,
+    elem: Element,
+    sprites: &SpriteCatalogue,
+    rng: &mut impl Rng,
+) -> (Enemy, Behaviour, usize) {
+    let (sprite, health) = match typ {
+        EnemyType::Chicken => ("enemy.chicken", 1.),
+        EnemyType::Cat => ("enemy.cat", 2.),
+        EnemyType::Dog => ("enemy.dog", 3.),
+        EnemyType::YoungOld => ("enemy.young_old", 4.),
+        EnemyType::Man => ("enemy.man", 5.),
+        EnemyType::Money => ("enemy.money", 0.),
+        EnemyType::Battery => ("enemy.battery", 0.),
+        EnemyType::EndGame => ("enemy.end_game", 1.),
     };
+    let index = sprites.get(sprite).pick(rng);
 
     (
         Enemy {
             pos,
             health,
-            elem: match typ {
-                EnemyType::Money | EnemyType::Battery => Element::Basic,
-                _ => enemy_elem(),
-            },
             typ,
+            elem,
         },
+        Behaviour::new(typ),
         index,
     )
 }
 
-fn enemy_type(level: u32) -> EnemyType {
-    let rnd = rand::random::<u32>() % 100;
+/// Removes an enemy that ran out of health, giving its reward
+/// The money dropped is drawn from `game_rng`, sounds are only cosmetic
+pub(crate) fn kill_enemy(
+    cmd: &mut Commands,
+    entity: Entity,
+    enemy: &Enemy,
+    sound_assets: &SoundAssets,
+    save_data: &mut SaveData,
+    tilemap: &mut Tilemap,
+    game_rng: &mut impl Rng,
+    kill_writer: &mut EventWriter<KillEvent>,
+) {
+    cmd.entity(entity).despawn();
+    tilemap.set_occupant(enemy.pos, None);
+    let mut rng = rand::thread_rng();
+    cmd.spawn(AudioBundle {
+        source: match enemy.typ {
+            EnemyType::Chicken => sound_assets.chicken[rng.gen_range(0..2)].clone(),
+            EnemyType::Cat => sound_assets.cat[rng.gen_range(0..3)].clone(),
+            EnemyType::Dog => sound_assets.dog[rng.gen_range(0..3)].clone(),
+            EnemyType::YoungOld | EnemyType::Man => sound_assets.man[rng.gen_range(0..2)].clone(),
+            EnemyType::EndGame | EnemyType::Money | EnemyType::Battery => {
+                sound_assets.upgrades[rng.gen_range(0..2)].clone()
+            },
+        },
+        settings: PlaybackSettings::DESPAWN,
+    });
+    let money = match enemy.typ {
+        EnemyType::Chicken => game_rng.gen_range(4..6),
+        EnemyType::Cat => game_rng.gen_range(8..11),
+        EnemyType::Dog => game_rng.gen_range(14..17),
+        EnemyType::YoungOld => game_rng.gen_range(18..21),
+        EnemyType::Man => game_rng.gen_range(24..27),
+        EnemyType::Money => game_rng.gen_range((save_data.level + 2)..(save_data.level + 1) * 4),
+        EnemyType::EndGame | EnemyType::Battery => 0,
+    };
+    save_data.money += money;
+    if !matches!(enemy.typ, EnemyType::Money) {
+        save_data.enemies_killed += 1;
+    };
+    kill_writer.send(KillEvent {
+        typ: enemy.typ,
+        elem: enemy.elem,
+        money,
+    });
+}
+
+/// Moves an enemy one tile if there is space behind it
+fn push_enemy(
+    cmd: &mut Commands,
+    entity: Entity,
+    enemy: &mut Enemy,
+    dir: IVec2,
+    tilemap: &mut Tilemap,
+) {
+    let pos = enemy.pos + dir;
+    let Some(cell) = tilemap.get(pos) else { return };
+    if cell.tile != Tile::Ground || cell.occupant.is_some() {
+        return;
+    }
+    cmd.entity(entity).insert(MoveTo::new(
+        tile_to_pos(enemy.pos),
+        tile_to_pos(pos),
+        None,
+    ));
+    tilemap.move_occupant(enemy.pos, pos);
+    enemy.pos = pos;
+}
+
+/// Energy gained each tick, dogs act twice for each move of the player and
+/// kids and elders every other move
+pub fn speed(typ: EnemyType) -> u32 {
+    match typ {
+        EnemyType::Dog => NORMAL_SPEED * 2,
+        EnemyType::YoungOld => NORMAL_SPEED / 2,
+        _ => NORMAL_SPEED,
+    }
+}
+
+/// Battery drained when an enemy attacks the player
+/// Bigger enemies hit harder, and elemental ones add a bit more on top
+pub fn hit_damage(typ: EnemyType, elem: Element) -> u32 {
+    let base = match typ {
+        EnemyType::Chicken => 1,
+        EnemyType::Cat => 2,
+        EnemyType::Dog | EnemyType::YoungOld => 3,
+        EnemyType::Man => 4,
+        EnemyType::Money | EnemyType::Battery | EnemyType::EndGame => return 0,
+    };
+    let extra = match elem {
+        Element::Basic => 0,
+        Element::Fire => 2,
+        Element::Water | Element::Grass => 1,
+    };
+    base + extra
+}
+
+/// Shows a number that floats up from a tile and fades away
+pub(crate) fn spawn_damage_text(
+    cmd: &mut Commands,
+    assets: &CoreAssets,
+    text: String,
+    color: Color,
+    pos: IVec2,
+) {
+    cmd.spawn((
+        Text2dBundle {
+            text: Text::from_section(text, TextStyle {
+                font: assets.font.clone(),
+                font_size: 10.,
+                color,
+            }),
+            transform: Transform::from_translation(tile_to_pos(pos).extend(15.)),
+            ..default()
+        },
+        DamageText(
+            Timer::from_seconds(0.3, TimerMode::Once),
+            tile_to_pos(pos),
+        ),
+    ));
+}
+
+fn enemy_type(level: u32, weights: &[[u32; 7]], rng: &mut impl Rng) -> EnemyType {
+    let rnd = rng.gen_range(0..100);
     let mut typ = 0;
     let mut cum_w = 0;
-    for w in WEIGHTS[level as usize].iter() {
+    for w in weights[level as usize].iter() {
         cum_w += w;
         if rnd < cum_w {
             break;
@@ -522,8 +681,7 @@ fn enemy_type(level: u32) -> EnemyType {
     }
 }
 
-fn enemy_elem() -> Element {
-    let mut rng = rand::thread_rng();
+fn enemy_elem(rng: &mut impl Rng) -> Element {
     match rng.gen_range(0..4) {
         0 => Element::Fire,
         1 => Element::Water,
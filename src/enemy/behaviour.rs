@@ -0,0 +1,394 @@
+//! Enemy behaviour submodule
+//! Each species gets a `Behaviour` with the parameters of how it acts, and
+//! `decide` turns them into what the enemy wants to do this turn. It only
+//! depends on the data in `AiContext`, so it can run without an app
+
+use bevy::prelude::*;
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    enemy::EnemyType,
+    misc::{dir_to_vec, Direction},
+    tilemap::{
+        pathfinding::{find_path, PlayerDistances},
+        Room,
+    },
+};
+
+/// Turns that an enemy keeps chasing the player after being called
+pub const ALERT_TURNS: u32 = 6;
+
+// ··········
+// Components
+// ··········
+
+/// How an enemy acts on its turn
+/// The checks are done in order: call, pounce, chase, flee, patrol and wander
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Behaviour {
+    /// Chance of staying still instead of wandering
+    pub rest: f64,
+    /// Runs away when the player is this close
+    pub flee: Option<u32>,
+    /// Goes after the player when it is this close
+    pub chase: Option<u32>,
+    /// Attacks when the player is right next to it
+    pub pounce: bool,
+    /// Walks from room to room instead of wandering
+    pub patrol: bool,
+    /// Calls every enemy this close when it sees the player
+    pub call: Option<u32>,
+}
+
+impl Default for Behaviour {
+    /// Never moves, used for items
+    fn default() -> Self {
+        Self {
+            rest: 1.,
+            flee: None,
+            chase: None,
+            pounce: false,
+            patrol: false,
+            call: None,
+        }
+    }
+}
+
+impl Behaviour {
+    pub fn new(typ: EnemyType) -> Self {
+        match typ {
+            EnemyType::Chicken => Self {
+                rest: 0.3,
+                flee: Some(3),
+                ..default()
+            },
+            EnemyType::Cat => Self {
+                rest: 0.5,
+                pounce: true,
+                ..default()
+            },
+            EnemyType::Dog => Self {
+                rest: 0.3,
+                chase: Some(6),
+                pounce: true,
+                ..default()
+            },
+            EnemyType::YoungOld => Self {
+                rest: 0.3,
+                patrol: true,
+                call: Some(5),
+                ..default()
+            },
+            EnemyType::Man => Self {
+                rest: 0.2,
+                pounce: true,
+                patrol: true,
+                call: Some(7),
+                ..default()
+            },
+            EnemyType::Money | EnemyType::Battery | EnemyType::EndGame => Self::default(),
+        }
+    }
+
+    /// If this behaviour never makes the enemy move
+    pub fn is_still(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// What an enemy remembers between turns
+#[derive(Component, Clone, Default, Debug, Serialize, Deserialize)]
+pub struct AiState {
+    /// Room center that a patrolling enemy is walking to
+    pub target: Option<IVec2>,
+    /// Turns left chasing the player after being called
+    pub alerted: u32,
+    /// Already called for help since the player came close
+    pub called: bool,
+}
+
+// ·······
+// Helpers
+// ·······
+
+/// Everything an enemy knows about the level when deciding
+pub struct AiContext<'a> {
+    pub pos: IVec2,
+    pub player: IVec2,
+    pub distances: &'a PlayerDistances,
+    pub rooms: &'a [Room],
+    /// Cost of walking into a tile, `None` if it can't be walked on
+    pub cost: &'a dyn Fn(IVec2) -> Option<u32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Intent {
+    Stay,
+    /// Moves to a neighbour tile, or attacks if the player is there
+    Step(IVec2),
+    /// Stays and alerts the enemies around
+    Call,
+}
+
+pub fn decide(
+    behaviour: &Behaviour,
+    state: &mut AiState,
+    ctx: &AiContext,
+    rng: &mut impl Rng,
+) -> Intent {
+    let dist = ctx.distances.get(ctx.pos);
+    let within = |radius: Option<u32>| radius.zip(dist).is_some_and(|(r, d)| d <= r);
+
+    // Call for help once each time the player gets close
+    if behaviour.call.is_some() {
+        if !within(behaviour.call) {
+            state.called = false;
+        } else if !state.called {
+            state.called = true;
+            state.alerted = ALERT_TURNS;
+            return Intent::Call;
+        }
+    }
+
+    let adjacent = (ctx.player - ctx.pos).abs().element_sum() == 1;
+    if behaviour.pounce && adjacent {
+        return Intent::Step(ctx.player);
+    }
+
+    if state.alerted > 0 || within(behaviour.chase) {
+        state.alerted = state.alerted.saturating_sub(1);
+        if let Some(next) = ctx.distances.step_toward(ctx.pos) {
+            return Intent::Step(next);
+        }
+    }
+
+    if within(behaviour.flee) {
+        if let Some(next) = ctx.distances.step_away(ctx.pos) {
+            return Intent::Step(next);
+        }
+    }
+
+    if rng.gen_bool(behaviour.rest.clamp(0., 1.)) {
+        return Intent::Stay;
+    }
+
+    if behaviour.patrol && !ctx.rooms.is_empty() {
+        if state.target.is_none() || state.target == Some(ctx.pos) {
+            state.target = ctx.rooms.choose(rng).map(|room| room.center());
+        }
+        let next = state
+            .target
+            .and_then(|target| find_path(ctx.pos, target, ctx.cost))
+            .and_then(|path| path.first().copied());
+        match next {
+            Some(next) => return Intent::Step(next),
+            None => state.target = None,
+        }
+    }
+
+    let dir: Direction = rng.gen();
+    Intent::Step(ctx.pos + dir_to_vec(&dir, 1.).as_ivec2())
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::tilemap::{Tile, TileCell, Tilemap};
+
+    const PLAYER: IVec2 = IVec2::ZERO;
+
+    /// Open ground with the player in the bottom left corner
+    fn ground(size: UVec2) -> Tilemap {
+        let mut tilemap = Tilemap::new(IVec2::ZERO, size);
+        for x in 0..size.x as i32 {
+            for y in 0..size.y as i32 {
+                tilemap.insert(IVec2::new(x, y), TileCell {
+                    tile: Tile::Ground,
+                    entity: Entity::PLACEHOLDER,
+                    occupant: None,
+                });
+            }
+        }
+        tilemap
+    }
+
+    /// Two rooms three tiles wide joined by a corridor in the middle row
+    fn corridor() -> Tilemap {
+        let mut tilemap = ground(UVec2::new(11, 3));
+        for x in 3..8 {
+            for y in 0..3 {
+                let tile = if y == 1 { Tile::Path } else { Tile::Wall };
+                tilemap.insert(IVec2::new(x, y), TileCell {
+                    tile,
+                    entity: Entity::PLACEHOLDER,
+                    occupant: None,
+                });
+            }
+        }
+        tilemap
+    }
+
+    /// Decides once for an enemy at `pos` in open ground
+    fn decide_at(behaviour: &Behaviour, state: &mut AiState, pos: IVec2, rooms: &[Room]) -> Intent {
+        decide_on(
+            &ground(UVec2::new(10, 6)),
+            behaviour,
+            state,
+            pos,
+            rooms,
+        )
+    }
+
+    fn decide_on(
+        tilemap: &Tilemap,
+        behaviour: &Behaviour,
+        state: &mut AiState,
+        pos: IVec2,
+        rooms: &[Room],
+    ) -> Intent {
+        let distances = PlayerDistances::new(PLAYER, |pos| tilemap.enemy_cost(pos));
+        let cost = |pos| tilemap.enemy_cost(pos);
+        let ctx = AiContext {
+            pos,
+            player: PLAYER,
+            distances: &distances,
+            rooms,
+            cost: &cost,
+        };
+        decide(
+            behaviour,
+            state,
+            &ctx,
+            &mut StdRng::seed_from_u64(0),
+        )
+    }
+
+    #[test]
+    fn dogs_chase_and_pounce() {
+        // A dog that never wanders, so it only moves when it chases
+        let dog = Behaviour {
+            rest: 1.,
+            ..Behaviour::new(EnemyType::Dog)
+        };
+        let mut state = AiState::default();
+
+        let intent = decide_at(&dog, &mut state, IVec2::new(4, 0), &[]);
+        assert_eq!(intent, Intent::Step(IVec2::new(3, 0)));
+        // Too far away to notice
+        let intent = decide_at(&dog, &mut state, IVec2::new(9, 5), &[]);
+        assert_eq!(intent, Intent::Stay);
+
+        let intent = decide_at(&dog, &mut state, IVec2::new(1, 0), &[]);
+        assert_eq!(intent, Intent::Step(PLAYER));
+    }
+
+    #[test]
+    fn chickens_flee() {
+        let chicken = Behaviour::new(EnemyType::Chicken);
+        let pos = IVec2::new(2, 0);
+
+        let Intent::Step(next) = decide_at(
+            &chicken,
+            &mut AiState::default(),
+            pos,
+            &[],
+        ) else {
+            panic!("the chicken should move");
+        };
+        assert_eq!(next.element_sum(), 3);
+    }
+
+    #[test]
+    fn callers_call_once_and_then_chase() {
+        let young_old = Behaviour::new(EnemyType::YoungOld);
+        let mut state = AiState::default();
+        let pos = IVec2::new(3, 0);
+
+        let intent = decide_at(&young_old, &mut state, pos, &[]);
+        assert_eq!(intent, Intent::Call);
+        let intent = decide_at(&young_old, &mut state, pos, &[]);
+        assert_eq!(intent, Intent::Step(IVec2::new(2, 0)));
+        assert_eq!(state.alerted, ALERT_TURNS - 1);
+    }
+
+    #[test]
+    fn patrols_walk_to_a_room() {
+        let patrol = Behaviour {
+            rest: 0.,
+            patrol: true,
+            ..default()
+        };
+        let room = Room {
+            min: IVec2::new(6, 2),
+            max: IVec2::new(8, 4),
+        };
+        let mut state = AiState::default();
+        let pos = IVec2::new(9, 0);
+
+        let Intent::Step(next) = decide_at(&patrol, &mut state, pos, &[room]) else {
+            panic!("the patrol should move");
+        };
+        assert_eq!(state.target, Some(room.center()));
+        let dist = |pos: IVec2| (room.center() - pos).abs().element_sum();
+        assert_eq!(dist(next), dist(pos) - 1);
+    }
+
+    #[test]
+    fn enemies_walk_through_corridors() {
+        let tilemap = corridor();
+        let rooms = [
+            Room {
+                min: IVec2::new(0, 0),
+                max: IVec2::new(2, 2),
+            },
+            Room {
+                min: IVec2::new(8, 0),
+                max: IVec2::new(10, 2),
+            },
+        ];
+
+        // Patrolling to the other room goes into the corridor
+        let patrol = Behaviour {
+            rest: 0.,
+            patrol: true,
+            ..default()
+        };
+        let mut state = AiState {
+            target: Some(rooms[1].center()),
+            ..default()
+        };
+        let intent = decide_on(
+            &tilemap,
+            &patrol,
+            &mut state,
+            IVec2::new(2, 1),
+            &rooms,
+        );
+        assert_eq!(intent, Intent::Step(IVec2::new(3, 1)));
+        assert_eq!(
+            tilemap.enemy_cost(IVec2::new(3, 1)),
+            Some(1)
+        );
+
+        // A called dog crosses it to reach the player
+        let dog = Behaviour {
+            rest: 1.,
+            ..Behaviour::new(EnemyType::Dog)
+        };
+        let mut state = AiState {
+            alerted: ALERT_TURNS,
+            ..default()
+        };
+        let intent = decide_on(
+            &tilemap,
+            &dog,
+            &mut state,
+            IVec2::new(8, 1),
+            &rooms,
+        );
+        assert_eq!(intent, Intent::Step(IVec2::new(7, 1)));
+    }
+}
@@ -0,0 +1,186 @@
+//! Element chart submodule
+//! How effective each element is against the others, read from
+//! `assets/data/elements.chart.ron` so it can be rebalanced without code
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{assets::RonAsset, data::SaveData, enemy::Element};
+
+// ······
+// Assets
+// ······
+
+/// Effectiveness of every pair of elements
+#[derive(Asset, Resource, TypePath, Deserialize, Clone, Debug, Default)]
+pub struct ElementChart {
+    pub matchups: Vec<Matchup>,
+}
+
+/// What happens when an element attacks another one
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Matchup {
+    pub attack: Element,
+    pub defense: Element,
+    /// Scales the damage of the attack
+    pub multiplier: f32,
+    /// Part of the attack that drains the battery instead
+    #[serde(default)]
+    pub backfire: f32,
+    /// If it spends one of the elemental charges
+    #[serde(default)]
+    pub uses_charge: bool,
+}
+
+/// Result of an attack after checking the chart
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Outcome {
+    pub damage: f32,
+    /// Battery lost by the player
+    pub backfire: u32,
+    pub charge_used: bool,
+}
+
+impl ElementChart {
+    /// Returns the matchup between two elements
+    /// Missing pairs do normal damage without spending charges
+    pub fn get(&self, attack: Element, defense: Element) -> Matchup {
+        self.matchups
+            .iter()
+            .find(|m| m.attack == attack && m.defense == defense)
+            .copied()
+            .unwrap_or(Matchup {
+                attack,
+                defense,
+                multiplier: 1.,
+                backfire: 0.,
+                uses_charge: false,
+            })
+    }
+
+    /// Resolves an attack with a base power
+    /// `charges` are the uses left of the attacking element, `None` if it has
+    /// no limit. If a charge is needed and there are none, nothing happens
+    pub fn outcome(
+        &self,
+        attack: Element,
+        defense: Element,
+        power: f32,
+        charges: Option<u32>,
+    ) -> Outcome {
+        let matchup = self.get(attack, defense);
+        if matchup.uses_charge && charges == Some(0) {
+            return Outcome::default();
+        }
+        Outcome {
+            damage: power * matchup.multiplier,
+            backfire: (power * matchup.backfire) as u32,
+            charge_used: matchup.uses_charge && charges.is_some(),
+        }
+    }
+}
+
+impl RonAsset for ElementChart {
+    type File = Self;
+
+    const EXTENSION: &'static str = "chart.ron";
+
+    fn from_file(file: Self) -> Result<Self, String> {
+        Ok(file)
+    }
+}
+
+// ·······
+// Helpers
+// ·······
+
+/// Uses left of an element, `None` if it has no limit
+pub fn charges(save_data: &mut SaveData, elem: Element) -> Option<&mut u32> {
+    match elem {
+        Element::Basic => None,
+        Element::Fire => Some(&mut save_data.fire_uses),
+        Element::Water => Some(&mut save_data.water_uses),
+        Element::Grass => Some(&mut save_data.grass_uses),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chart() -> ElementChart {
+        let bytes = std::fs::read("assets/data/elements.chart.ron").unwrap();
+        ron::de::from_bytes(&bytes).unwrap()
+    }
+
+    #[test]
+    fn same_element_does_normal_damage() {
+        let chart = chart();
+        for elem in [
+            Element::Basic,
+            Element::Fire,
+            Element::Water,
+            Element::Grass,
+        ] {
+            let outcome = chart.outcome(elem, elem, 2., Some(3));
+            assert_eq!(outcome.damage, 2., "{:?}", elem);
+            assert_eq!(outcome.backfire, 0, "{:?}", elem);
+        }
+    }
+
+    #[test]
+    fn weak_attacks_backfire() {
+        let chart = chart();
+        assert_eq!(
+            chart.outcome(
+                Element::Fire,
+                Element::Water,
+                3.,
+                Some(1)
+            ),
+            Outcome {
+                damage: 0.,
+                backfire: 3,
+                charge_used: true,
+            }
+        );
+        assert_eq!(
+            chart
+                .outcome(
+                    Element::Water,
+                    Element::Fire,
+                    2.,
+                    Some(1)
+                )
+                .damage,
+            3.
+        );
+    }
+
+    #[test]
+    fn attacks_without_charges_do_nothing() {
+        let chart = chart();
+        assert_eq!(
+            chart.outcome(
+                Element::Grass,
+                Element::Water,
+                2.,
+                Some(0)
+            ),
+            Outcome::default()
+        );
+        // The basic attack has no charges to spend
+        let outcome = chart.outcome(Element::Basic, Element::Fire, 2., None);
+        assert_eq!(outcome.damage, 2.);
+        assert!(!outcome.charge_used);
+        // Pairs missing from the chart do normal damage
+        let outcome = ElementChart::default().outcome(
+            Element::Fire,
+            Element::Grass,
+            2.,
+            Some(0),
+        );
+        assert_eq!(outcome.damage, 2.);
+        assert!(!outcome.charge_used);
+    }
+}
@@ -0,0 +1,88 @@
+//! Enemy snapshot submodule
+//! Enemies saved with everything needed to spawn them again, used to undo
+//! turns and to suspend a level
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assets::SpriteAssets,
+    enemy::{AiState, Behaviour, Burning, Enemy, Rooted, Soaked},
+    tilemap::{spawn_enemy, EnemySpawn, Tilemap},
+};
+
+/// Enemies with what `EnemySnapshot` saves
+pub type SnapshotQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Enemy,
+        &'static Behaviour,
+        &'static AiState,
+        &'static TextureAtlas,
+        Option<&'static Burning>,
+        Option<&'static Soaked>,
+        Option<&'static Rooted>,
+    ),
+>;
+
+/// Everything needed to spawn an enemy again
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EnemySnapshot {
+    /// Entity when the snapshot was taken, used to restore the turn order
+    pub entity: Entity,
+    pub spawn: EnemySpawn,
+    pub state: AiState,
+    pub burning: Option<Burning>,
+    pub soaked: Option<Soaked>,
+    pub rooted: Option<Rooted>,
+}
+
+impl EnemySnapshot {
+    /// Saves every enemy in the level
+    pub fn take_all(enemies: &SnapshotQuery) -> Vec<Self> {
+        enemies
+            .iter()
+            .map(
+                |(entity, enemy, behaviour, state, atlas, burning, soaked, rooted)| Self {
+                    entity,
+                    spawn: EnemySpawn {
+                        enemy: enemy.clone(),
+                        behaviour: behaviour.clone(),
+                        sprite: atlas.index,
+                    },
+                    state: state.clone(),
+                    burning: burning.copied(),
+                    soaked: soaked.copied(),
+                    rooted: rooted.copied(),
+                },
+            )
+            .collect()
+    }
+
+    /// Spawns the enemy with its state and effects
+    /// The new entity should take the place of `entity` in the turn order
+    pub fn respawn(
+        self,
+        cmd: &mut Commands,
+        sprite_assets: &SpriteAssets,
+        tilemap: &mut Tilemap,
+    ) -> Entity {
+        let pos = self.spawn.enemy.pos;
+        let entity = spawn_enemy(cmd, sprite_assets, self.spawn);
+        let mut enemy = cmd.entity(entity);
+        enemy.insert(self.state);
+        if let Some(burning) = self.burning {
+            enemy.insert(burning);
+        }
+        if let Some(soaked) = self.soaked {
+            enemy.insert(soaked);
+        }
+        if let Some(rooted) = self.rooted {
+            enemy.insert(rooted);
+        }
+        tilemap.set_occupant(pos, Some(entity));
+        entity
+    }
+}
@@ -0,0 +1,319 @@
+//! Status effects submodule
+//! Elemental attacks leave effects on enemies that last a few turns. They tick
+//! when the enemy turn starts, before anyone moves
+//!
+//! - Burning (fire): loses health every turn, stacks up to `MAX_BURN_STACKS`
+//! - Soaked (water): loses its turns, puts out fire
+//! - Rooted (grass): can't move but still attacks, fire burns the roots away
+//!   with bonus damage
+//!
+//! Enemies are immune to the effect of their own element
+
+use bevy::{color::Mix, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assets::{CoreAssets, SoundAssets},
+    data::{Persistent, SaveData},
+    enemy::{enemy_color, kill_enemy, spawn_damage_text, Element, Enemy, EnemyFlash, KillEvent},
+    tilemap::{GameRng, Tilemap},
+};
+
+pub const BURN_TURNS: u32 = 3;
+/// Health lost per turn for each stack
+pub const BURN_DAMAGE: f32 = 0.2;
+pub const MAX_BURN_STACKS: u32 = 3;
+pub const SOAK_TURNS: u32 = 2;
+pub const ROOT_TURNS: u32 = 3;
+/// Damage multiplier of fire attacks against rooted enemies
+pub const ROOTED_FIRE_BONUS: f32 = 1.5;
+
+const BURNING_TINT: Color = Color::srgb(1., 0.5, 0.1);
+const SOAKED_TINT: Color = Color::srgb(0.2, 0.3, 1.);
+const ROOTED_TINT: Color = Color::srgb(0.4, 0.3, 0.1);
+
+// ··········
+// Components
+// ··········
+
+#[derive(Component, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Burning {
+    pub turns: u32,
+    pub stacks: u32,
+}
+
+#[derive(Component, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Soaked {
+    pub turns: u32,
+}
+
+#[derive(Component, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Rooted {
+    pub turns: u32,
+}
+
+// ·······
+// Systems
+// ·······
+
+/// Resolves the effects at the start of the enemy turn
+/// An effect with `turns` left affects that many enemy turns
+pub(crate) fn tick_status(
+    mut cmd: Commands,
+    mut burning: Query<(Entity, &mut Enemy, &mut Burning)>,
+    mut soaked: Query<(Entity, &mut Soaked)>,
+    mut rooted: Query<(Entity, &mut Rooted)>,
+    mut save_data: ResMut<Persistent<SaveData>>,
+    mut tilemap: ResMut<Tilemap>,
+    mut rng: ResMut<GameRng>,
+    sound_assets: Res<SoundAssets>,
+    assets: Res<CoreAssets>,
+    mut kill_writer: EventWriter<KillEvent>,
+) {
+    for (entity, mut enemy, mut burn) in burning.iter_mut() {
+        if !tick(&mut burn.turns) {
+            cmd.entity(entity).remove::<Burning>();
+            continue;
+        }
+
+        let value = (BURN_DAMAGE * burn.stacks as f32).min(enemy.health);
+        enemy.health -= value;
+        spawn_damage_text(
+            &mut cmd,
+            &assets,
+            format!("{:.1}", value),
+            BURNING_TINT,
+            enemy.pos,
+        );
+        if enemy.health <= 0. {
+            kill_enemy(
+                &mut cmd,
+                entity,
+                &enemy,
+                &sound_assets,
+                &mut save_data,
+                &mut tilemap,
+                &mut rng.0,
+                &mut kill_writer,
+            );
+        }
+    }
+
+    for (entity, mut soak) in soaked.iter_mut() {
+        if !tick(&mut soak.turns) {
+            cmd.entity(entity).remove::<Soaked>();
+        }
+    }
+
+    for (entity, mut root) in rooted.iter_mut() {
+        if !tick(&mut root.turns) {
+            cmd.entity(entity).remove::<Rooted>();
+        }
+    }
+}
+
+/// Mixes the color of the enemy element with the color of its effects
+pub(crate) fn status_tint(
+    mut enemies: Query<
+        (
+            &Enemy,
+            &mut Sprite,
+            Has<Burning>,
+            Has<Soaked>,
+            Has<Rooted>,
+        ),
+        Without<EnemyFlash>,
+    >,
+) {
+    for (enemy, mut sprite, burning, soaked, rooted) in enemies.iter_mut() {
+        let mut color = enemy_color(&enemy.elem);
+        for (active, tint) in [
+            (burning, BURNING_TINT),
+            (soaked, SOAKED_TINT),
+            (rooted, ROOTED_TINT),
+        ] {
+            if active {
+                color = color.mix(&tint, 0.5);
+            }
+        }
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
+
+// ·······
+// Helpers
+// ·······
+
+/// Counts down an effect at the start of an enemy turn
+/// Returns false once it has affected all of its turns and has to be removed
+fn tick(turns: &mut u32) -> bool {
+    if *turns == 0 {
+        return false;
+    }
+    *turns -= 1;
+    true
+}
+
+/// Adds the effect of an elemental hit to an enemy, following the stacking
+/// rules and interactions between effects
+pub(crate) fn apply_status(
+    cmd: &mut Commands,
+    entity: Entity,
+    attack: Element,
+    enemy: &Enemy,
+    effects: (
+        Option<&Burning>,
+        Option<&Soaked>,
+        Option<&Rooted>,
+    ),
+) {
+    if attack == enemy.elem {
+        return;
+    }
+    let Some(mut entity) = cmd.get_entity(entity) else { return };
+    let (burning, soaked, rooted) = effects;
+
+    match attack {
+        Element::Basic => {},
+        Element::Fire => {
+            if rooted.is_some() {
+                entity.remove::<Rooted>();
+            }
+            // Water turns into steam instead of catching fire
+            if soaked.is_some() {
+                entity.remove::<Soaked>();
+                return;
+            }
+            let stacks = burning.map_or(0, |burn| burn.stacks);
+            entity.try_insert(Burning {
+                turns: BURN_TURNS,
+                stacks: (stacks + 1).min(MAX_BURN_STACKS),
+            });
+        },
+        Element::Water => {
+            entity.remove::<Burning>();
+            entity.try_insert(Soaked { turns: SOAK_TURNS });
+        },
+        Element::Grass => {
+            // Roots don't grow again until they are gone
+            if rooted.is_none() {
+                entity.try_insert(Rooted { turns: ROOT_TURNS });
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::world::CommandQueue;
+
+    use super::*;
+    use crate::enemy::EnemyType;
+
+    /// Hits an enemy with an element, with the effects it already has
+    fn hit(world: &mut World, entity: Entity, attack: Element) {
+        let mut queue = CommandQueue::default();
+        let mut cmd = Commands::new(&mut queue, world);
+        let entity_ref = world.entity(entity);
+        apply_status(
+            &mut cmd,
+            entity,
+            attack,
+            entity_ref.get::<Enemy>().unwrap(),
+            (
+                entity_ref.get::<Burning>(),
+                entity_ref.get::<Soaked>(),
+                entity_ref.get::<Rooted>(),
+            ),
+        );
+        queue.apply(world);
+    }
+
+    fn spawn(world: &mut World, elem: Element) -> Entity {
+        world
+            .spawn(Enemy {
+                pos: IVec2::ZERO,
+                health: 1.,
+                typ: EnemyType::Cat,
+                elem,
+            })
+            .id()
+    }
+
+    #[test]
+    fn burns_stack_up_to_a_limit() {
+        let mut world = World::new();
+        let enemy = spawn(&mut world, Element::Basic);
+
+        for stacks in 1..=MAX_BURN_STACKS + 2 {
+            hit(&mut world, enemy, Element::Fire);
+            let burn = world.get::<Burning>(enemy).unwrap();
+            assert_eq!(burn.stacks, stacks.min(MAX_BURN_STACKS));
+            assert_eq!(burn.turns, BURN_TURNS);
+        }
+    }
+
+    #[test]
+    fn water_and_fire_cancel_out() {
+        let mut world = World::new();
+        let enemy = spawn(&mut world, Element::Basic);
+
+        // Water puts out fire
+        hit(&mut world, enemy, Element::Fire);
+        hit(&mut world, enemy, Element::Water);
+        assert!(world.get::<Burning>(enemy).is_none());
+        assert!(world.get::<Soaked>(enemy).is_some());
+
+        // Fire on a soaked enemy turns into steam
+        hit(&mut world, enemy, Element::Fire);
+        assert!(world.get::<Soaked>(enemy).is_none());
+        assert!(world.get::<Burning>(enemy).is_none());
+    }
+
+    #[test]
+    fn roots_burn_and_dont_grow_again() {
+        let mut world = World::new();
+        let enemy = spawn(&mut world, Element::Basic);
+
+        hit(&mut world, enemy, Element::Grass);
+        world.get_mut::<Rooted>(enemy).unwrap().turns = 1;
+        hit(&mut world, enemy, Element::Grass);
+        assert_eq!(
+            world.get::<Rooted>(enemy).unwrap().turns,
+            1
+        );
+
+        hit(&mut world, enemy, Element::Fire);
+        assert!(world.get::<Rooted>(enemy).is_none());
+        assert!(world.get::<Burning>(enemy).is_some());
+    }
+
+    #[test]
+    fn enemies_are_immune_to_their_element() {
+        let mut world = World::new();
+        for elem in [Element::Fire, Element::Water, Element::Grass] {
+            let enemy = spawn(&mut world, elem);
+            hit(&mut world, enemy, elem);
+            let entity = world.entity(enemy);
+            assert!(!entity.contains::<Burning>());
+            assert!(!entity.contains::<Soaked>());
+            assert!(!entity.contains::<Rooted>());
+        }
+    }
+
+    #[test]
+    fn effects_last_their_turns() {
+        let affected = |mut turns| {
+            let mut count = 0;
+            while tick(&mut turns) {
+                count += 1;
+            }
+            count
+        };
+        assert_eq!(affected(BURN_TURNS), BURN_TURNS);
+        assert_eq!(affected(SOAK_TURNS), SOAK_TURNS);
+        assert_eq!(affected(ROOT_TURNS), ROOT_TURNS);
+    }
+}
@@ -5,7 +5,8 @@ pub use leafwing_input_manager::prelude::ActionState;
 use leafwing_input_manager::prelude::*;
 
 use crate::{
-    data::{Persistent, SaveData},
+    data::{GameOptions, Persistent, SaveData},
+    misc::{axis_to_dir, Direction},
     PlayState,
 };
 
@@ -21,14 +22,16 @@ pub struct InputPlugin;
 impl Plugin for InputPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(InputManagerPlugin::<Action>::default())
+            .init_resource::<MoveBuffer>()
             .add_systems(
                 OnEnter(PlayState::default()),
                 init.run_if(run_once()),
-            );
+            )
+            .add_systems(OnEnter(PlayState::Play), clear_buffer);
 
         app.add_systems(
             Update,
-            handle_input.in_set(crate::PlaySet::Tick),
+            (handle_input, buffer_move).in_set(crate::PlaySet::Tick),
         );
     }
 }
@@ -51,6 +54,27 @@ pub enum Action {
     AttackGrass,
     NextAttack,
     PreviousAttack,
+    /// Starts aiming an area attack with the selected element, and fires it
+    Aim,
+    /// Goes back to the start of the previous turn
+    Undo,
+}
+
+// ·········
+// Resources
+// ·········
+
+/// Movement waiting for the player to be able to act
+/// Presses made during the turn of the enemies are kept until it ends, and
+/// holding a direction repeats it using the delay and rate in `GameOptions`
+#[derive(Resource, Default)]
+pub struct MoveBuffer {
+    /// Next direction to move, only the latest one is kept
+    pub queued: Option<Direction>,
+    /// Direction being held down
+    held: Option<Direction>,
+    /// Time until the held direction is queued again
+    repeat: Timer,
 }
 
 // ·······
@@ -99,7 +123,12 @@ fn init(mut cmd: Commands) {
         .insert(
             Action::PreviousAttack,
             GamepadButtonType::South,
-        );
+        )
+        .insert(Action::Aim, KeyCode::Space)
+        .insert(Action::Aim, GamepadButtonType::West)
+        .insert(Action::Undo, KeyCode::KeyZ)
+        .insert(Action::Undo, KeyCode::Backspace)
+        .insert(Action::Undo, GamepadButtonType::Select);
 
     cmd.spawn(InputManagerBundle::with_map(input_map));
 }
@@ -139,3 +168,46 @@ fn handle_input(
         let _ = save_data.update(|data| data.attack_selected.prev());
     }
 }
+
+/// Queues movement presses and repeats held directions
+fn buffer_move(
+    input: Query<&ActionState<Action>>,
+    mut buffer: ResMut<MoveBuffer>,
+    options: Res<Persistent<GameOptions>>,
+    time: Res<Time>,
+) {
+    let Ok(input) = input.get_single() else { return };
+
+    let dir = input
+        .pressed(&Action::Move)
+        .then(|| input.clamped_axis_pair(&Action::Move))
+        .flatten()
+        .map(|axis| axis_to_dir(axis.xy()));
+    let Some(dir) = dir else {
+        buffer.held = None;
+        return;
+    };
+
+    if buffer.held != Some(dir) {
+        buffer.held = Some(dir);
+        buffer.queued = Some(dir);
+        buffer.repeat = Timer::from_seconds(
+            options.repeat_delay.max(0.),
+            TimerMode::Once,
+        );
+        return;
+    }
+
+    if buffer.repeat.tick(time.delta()).finished() {
+        buffer.queued = Some(dir);
+        buffer.repeat = Timer::from_seconds(
+            options.repeat_rate.max(0.05),
+            TimerMode::Once,
+        );
+    }
+}
+
+/// Forgets movement from before the game was paused or the level changed
+fn clear_buffer(mut buffer: ResMut<MoveBuffer>) {
+    *buffer = MoveBuffer::default();
+}
@@ -5,6 +5,7 @@
 #![allow(clippy::too_many_arguments)]
 #![allow(clippy::type_complexity)]
 
+pub mod achievement;
 pub mod assets;
 pub mod audio;
 pub mod camera;
@@ -14,9 +15,16 @@ pub mod enemy;
 pub mod input;
 pub mod misc;
 pub mod player;
+pub mod replay;
+pub mod sim;
+#[cfg(test)]
+mod testing;
 pub mod tilemap;
+pub mod turn;
 #[cfg(feature = "ui")]
 pub mod ui;
+#[cfg(feature = "input")]
+pub mod undo;
 
 use bevy::{log::LogPlugin, prelude::*, window::WindowResolution};
 
@@ -47,6 +55,9 @@ pub enum GameState {
     /// Upgrade shop
     Shop,
     UpdateShop,
+    /// Leaves `Play` for a frame to start the level again, used when the save
+    /// slot changes
+    Reload,
     /// End of the `Play` state, useful to restart the game
     End,
 }
@@ -66,8 +77,10 @@ pub enum PlayState {
 #[derive(SubStates, Debug, Default, Clone, Eq, PartialEq, Hash)]
 #[source(PlayState = PlayState::Play)]
 pub enum TurnState {
+    /// Waiting for the player to act
     #[default]
     Player,
+    /// The rest of the actors take their turns in the order of the `Scheduler`
     Enemy,
 }
 
@@ -95,6 +108,10 @@ pub struct AppConfig {
     /// The size of the canvas that renders a pixel perfect game
     #[cfg(feature = "pixel_perfect")]
     pub initial_game_res: Vec2,
+    /// Folder where the options, saves and replays are stored
+    pub data_dir: &'static str,
+    /// If every level played is saved as a replay
+    pub record_replays: bool,
 }
 
 impl Default for AppConfig {
@@ -104,6 +121,8 @@ impl Default for AppConfig {
             initial_window_res: (GAME_RES * SCALE).into(),
             #[cfg(feature = "pixel_perfect")]
             initial_game_res: GAME_RES,
+            data_dir: if cfg!(target_arch = "wasm32") { "local" } else { ".data" },
+            record_replays: true,
         }
     }
 }
@@ -190,6 +209,35 @@ impl Plugin for GamePlugin {
                 .set(asset_plugin),
         );
 
+        // Add the game rules
+        app.add_plugins(LogicPlugin);
+
+        // Add the rest of the plugins
+        app.add_plugins((
+            assets::AssetLoaderPlugin,
+            audio::AudioPlugin,
+            camera::CameraPlugin,
+        ));
+
+        #[cfg(feature = "input")]
+        app.add_plugins((input::InputPlugin, undo::UndoPlugin));
+
+        #[cfg(feature = "ui")]
+        app.add_plugins(ui::UiPlugin);
+    }
+}
+
+/// Game rules
+/// States, turns, levels and everything that happens in them, without any
+/// window, sound or interface. `GamePlugin` builds on top of it, and the
+/// headless simulation runs it on its own
+/// It needs the states plugin and assets loaded by another plugin
+pub struct LogicPlugin;
+
+impl Plugin for LogicPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AppConfig>();
+
         // Add the detailed schedule set
         app.configure_sets(
             Update,
@@ -211,24 +259,17 @@ impl Plugin for GamePlugin {
             .enable_state_scoped_entities::<PlayState>()
             .add_sub_state::<TurnState>();
 
-        // Add the rest of the plugins
         app.add_plugins((
-            assets::AssetLoaderPlugin,
-            audio::AudioPlugin,
-            camera::CameraPlugin,
+            achievement::AchievementPlugin,
             data::DataPlugin,
             enemy::EnemyPlugin,
             misc::MiscPlugin,
             player::PlayerPlugin,
+            replay::ReplayPlugin,
             tilemap::TilemapPlugin,
+            turn::TurnPlugin,
         ));
 
-        #[cfg(feature = "input")]
-        app.add_plugins(input::InputPlugin);
-
-        #[cfg(feature = "ui")]
-        app.add_plugins(ui::UiPlugin);
-
         app.add_systems(
             Update,
             finish_setup.run_if(in_state(GameState::Startup)),
@@ -247,3 +288,21 @@ impl Plugin for GamePlugin {
 fn finish_setup(mut next_state: ResMut<NextState<GameState>>) {
     next_state.set(GameState::Loading);
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{testing::TestApp, GameState, PlayState};
+
+    #[test]
+    fn loads_into_the_menu() {
+        let game = TestApp::new();
+        assert_eq!(
+            game.state::<GameState>(),
+            Some(&GameState::Play)
+        );
+        assert_eq!(
+            game.state::<PlayState>(),
+            Some(&PlayState::Menu)
+        );
+    }
+}
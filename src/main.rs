@@ -1,9 +1,16 @@
 use bevy::prelude::*;
-use kenney_jam::{AppConfig, GamePlugin};
+use kenney_jam::{replay, AppConfig, GamePlugin};
 
 fn main() {
+    let mut config = AppConfig::default();
+    // Replays overwrite the save data, so they get their own data folder
+    if replay::replay_arg().is_some() {
+        config.data_dir = replay::REPLAY_DATA_DIR;
+        config.record_replays = false;
+    }
+
     App::new()
-        .insert_resource(AppConfig::default())
+        .insert_resource(config)
         .add_plugins(GamePlugin)
         .run();
 }
@@ -5,6 +5,7 @@ use rand::{
     distributions::{Distribution, Standard},
     Rng,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{tilemap::TILE_SEP, GameState};
 
@@ -29,6 +30,7 @@ impl Plugin for MiscPlugin {
 // Components
 // ··········
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Direction {
     North,
     South,
@@ -112,6 +114,32 @@ fn move_to(
 // Helpers
 // ·······
 
+/// Direction of a unit vector, `None` if it is not one of the four
+pub fn vec_to_dir(vec: IVec2) -> Option<Direction> {
+    match (vec.x, vec.y) {
+        (0, 1) => Some(Direction::North),
+        (0, -1) => Some(Direction::South),
+        (1, 0) => Some(Direction::East),
+        (-1, 0) => Some(Direction::West),
+        _ => None,
+    }
+}
+
+/// Direction closest to a movement input
+pub fn axis_to_dir(axis: Vec2) -> Direction {
+    if axis.x.abs() > axis.y.abs() {
+        if axis.x > 0. {
+            Direction::East
+        } else {
+            Direction::West
+        }
+    } else if axis.y > 0. {
+        Direction::North
+    } else {
+        Direction::South
+    }
+}
+
 pub fn dir_to_vec(dir: &Direction, val: f32) -> Vec2 {
     match dir {
         Direction::North => Vec2::new(0., val),
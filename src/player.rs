@@ -1,20 +1,26 @@
 use bevy::{
     audio::{PlaybackMode, Volume},
-    color::palettes::css::{BLUE, GRAY, SILVER, WHITE, YELLOW},
+    color::palettes::css::{BLUE, GRAY, RED, SILVER, WHITE, YELLOW},
     prelude::*,
 };
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
+use self::aim::Aim;
 use crate::{
-    assets::{SoundAssets, SpriteAssets},
-    data::{max_battery, max_range, Persistent, SaveData},
-    enemy::{DamageEvent, Enemy},
-    input::{Action, ActionState},
+    assets::{CoreAssets, SoundAssets, SpriteAssets, SpriteCatalogue},
+    data::{max_range, Balance, Persistent, SaveData},
+    enemy::{enemy_color, spawn_damage_text, DamageEvent, Element, Enemy, PlayerHitEvent},
+    input::MoveBuffer,
     misc::{dir_to_vec, Direction, MoveTo},
-    tilemap::{tile_to_pos, Tile, Tilemap, ROOM_SEP},
+    replay::is_playing,
+    tilemap::{spawn_level, tile_to_pos, GameRng, LevelLayout, Tile, Tilemap},
+    turn::{Speed, NORMAL_SPEED},
     GameState, PlaySet, PlayState, TurnState, SCALE,
 };
 
+pub mod aim;
+
 const LOW_CONNECTION_PERCENTS: [f32; 5] = [0.5, 0.35, 0.2, 0.1, 0.0];
 
 // ······
@@ -26,18 +32,33 @@ pub struct PlayerPlugin;
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<StatusEvent>()
-            .add_systems(OnEnter(GameState::Play), init)
+            .add_event::<CommandEvent>()
+            .init_resource::<NextCommand>()
+            .add_systems(
+                OnEnter(GameState::Play),
+                (
+                    init.after(spawn_level),
+                    aim::init,
+                    clear_command,
+                ),
+            )
             .add_systems(
                 Update,
                 (
                     tick_wrong_move.in_set(PlaySet::Tick),
-                    move_player
+                    (
+                        (read_command, aim::aim_attack).run_if(not(is_playing)),
+                        move_player,
+                        aim::fire_area,
+                    )
+                        .chain()
                         .in_set(PlaySet::Move)
                         .run_if(in_state(TurnState::Player)),
                     check_player
                         .in_set(PlaySet::Collision)
                         .run_if(resource_changed::<Persistent<SaveData>>),
-                    on_status.in_set(PlaySet::Events),
+                    (on_hit, on_status).chain().in_set(PlaySet::Events),
+                    (player_flash, aim::update_highlight).in_set(PlaySet::Animation),
                 ),
             );
     }
@@ -55,10 +76,35 @@ pub struct Player {
 #[derive(Component)]
 struct WrongMove(Timer);
 
+#[derive(Component)]
+struct PlayerFlash(Timer);
+
+// ·········
+// Resources
+// ·········
+
+/// What the player does with its turn
+/// Commands come from the input or from a replay, and they are the only thing
+/// that needs to be recorded to play a level again
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Command {
+    /// Walks, or attacks an enemy in the way with an element
+    Move(Direction, Element),
+    /// Fires the area attack of an element
+    Area(Direction, Element),
+    /// Goes back to the start of the previous turn
+    Undo,
+}
+
+/// Command waiting to be carried out
+#[derive(Resource, Default)]
+pub struct NextCommand(pub Option<Command>);
+
 // ······
 // Events
 // ······
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
 pub enum Status {
     BatteryLow,
     BatteryEmpty,
@@ -69,13 +115,23 @@ pub enum Status {
 #[derive(Event)]
 pub struct StatusEvent(pub Status);
 
+/// A command was carried out
+#[derive(Event)]
+pub struct CommandEvent(pub Command);
+
 // ·······
 // Systems
 // ·······
 
-fn init(mut cmd: Commands, sprite_assets: Res<SpriteAssets>) {
-    let pos = ROOM_SEP.as_ivec2() / 2;
-    cmd.spawn((
+pub(crate) fn init(
+    mut cmd: Commands,
+    sprite_assets: Res<SpriteAssets>,
+    sprites: Res<SpriteCatalogue>,
+    layout: Res<LevelLayout>,
+    mut tilemap: ResMut<Tilemap>,
+) {
+    let pos = layout.ladder_up;
+    let entity = cmd.spawn((
         SpriteBundle {
             transform: Transform::from_translation(tile_to_pos(pos).extend(10.))
                 .with_scale(Vec3::splat(SCALE)),
@@ -84,25 +140,51 @@ fn init(mut cmd: Commands, sprite_assets: Res<SpriteAssets>) {
         },
         TextureAtlas {
             layout: sprite_assets.one_bit_atlas.clone(),
-            index: 25,
+            index: sprites.index("player"),
         },
         Player { pos },
+        Speed(NORMAL_SPEED),
         StateScoped(GameState::Play), // Every time the level changes this entity is destroyed
     ));
+    tilemap.set_occupant(pos, Some(entity.id()));
+}
+
+fn clear_command(mut next: ResMut<NextCommand>) {
+    next.0 = None;
+}
+
+/// Turns the buffered movement into the next command
+fn read_command(
+    player: Query<(), (With<Player>, Without<MoveTo>)>,
+    mut buffer: ResMut<MoveBuffer>,
+    aim: Res<Aim>,
+    save_data: Res<Persistent<SaveData>>,
+    mut next: ResMut<NextCommand>,
+) {
+    if next.0.is_some() || aim.0.is_some() || player.is_empty() {
+        return;
+    }
+    if let Some(dir) = buffer.queued.take() {
+        next.0 = Some(Command::Move(
+            dir,
+            save_data.attack_selected,
+        ));
+    }
 }
 
 fn move_player(
     mut cmd: Commands,
-    mut player: Query<(Entity, &mut Player)>,
-    enemies: Query<(Entity, &Enemy)>,
-    input: Query<&ActionState<Action>>,
-    tiles: Query<&Tile>,
-    tilemap: Res<Tilemap>,
+    mut player: Query<(Entity, &mut Player), Without<MoveTo>>,
+    enemies: Query<(), With<Enemy>>,
+    mut next: ResMut<NextCommand>,
+    mut tilemap: ResMut<Tilemap>,
+    mut rng: ResMut<GameRng>,
     sound_assets: Res<SoundAssets>,
     mut save_data: ResMut<Persistent<SaveData>>,
     mut next_play_state: ResMut<NextState<PlayState>>,
     mut next_turn_state: ResMut<NextState<TurnState>>,
     mut damage_writer: EventWriter<DamageEvent>,
+    mut command_writer: EventWriter<CommandEvent>,
 ) {
     if save_data.battery == 0 {
         return;
@@ -111,51 +193,40 @@ fn move_player(
     let Ok((entity, mut player)) = player.get_single_mut() else {
         return;
     };
-    let Ok(input) = input.get_single() else { return };
+    let Some(command @ Command::Move(input_dir, elem)) = next.0 else { return };
+    next.0 = None;
+    command_writer.send(CommandEvent(command));
+    if save_data.attack_selected != elem {
+        save_data.attack_selected = elem;
+    }
 
     let mut pos = player.pos;
 
-    if !input.pressed(&Action::Move) {
-        return;
-    }
-    let Some(axis) = input.clamped_axis_pair(&Action::Move) else { return };
-
     // Rooms left
     // 3 - 10%, 2 - 20%, 1 - 35%, 0 or more- 50%
     let rooms_left = max_range(save_data.range_level)
         .saturating_sub(save_data.level)
         .clamp(0, 4);
-    let random_input = LOW_CONNECTION_PERCENTS[rooms_left as usize] > rand::random::<f32>();
+    let random_input = LOW_CONNECTION_PERCENTS[rooms_left as usize] > rng.0.gen::<f32>();
 
     let dir = if random_input {
         cmd.entity(entity).insert(WrongMove(Timer::from_seconds(
             0.1,
             TimerMode::Once,
         )));
-        rand::thread_rng().gen()
-    } else if axis.x().abs() > axis.y().abs() {
-        if axis.x() > 0. {
-            Direction::East
-        } else {
-            Direction::West
-        }
-    } else if axis.y() > 0. {
-        Direction::North
+        rng.0.gen()
     } else {
-        Direction::South
+        input_dir
     };
 
     let movement = dir_to_vec(&dir, 1.).as_ivec2();
     pos += movement;
 
     let mut is_collision = false;
-    for (enemy_entity, enemy) in enemies.iter() {
-        if enemy.pos == pos {
-            is_collision = true;
-            damage_writer.send(DamageEvent(enemy_entity));
-            save_data.battery -= 1;
-            break;
-        }
+    if let Some(enemy_entity) = tilemap.occupant(pos).filter(|e| enemies.contains(*e)) {
+        is_collision = true;
+        damage_writer.send(DamageEvent::bump(enemy_entity, elem));
+        save_data.battery -= 1;
     }
 
     if !is_collision {
@@ -167,8 +238,7 @@ fn move_player(
                 ..default()
             },
         });
-        let Some(tile) = tilemap.get_tile(pos) else { return };
-        let Ok(tile) = tiles.get(tile) else { return };
+        let Some(tile) = tilemap.tile(pos) else { return };
         match tile {
             Tile::LadderUp => {
                 next_play_state.set(PlayState::ToShop);
@@ -189,13 +259,53 @@ fn move_player(
     next_turn_state.set(TurnState::Enemy);
 
     if !is_collision {
+        tilemap.move_occupant(player.pos, pos);
         player.pos = pos;
         save_data.battery -= 1;
     }
 }
 
-fn check_player(save_data: Res<Persistent<SaveData>>, mut status_writer: EventWriter<StatusEvent>) {
-    if save_data.battery < max_battery(save_data.battery_level) / 8 {
+fn on_hit(
+    mut cmd: Commands,
+    player: Query<(Entity, &Player)>,
+    mut hit_reader: EventReader<PlayerHitEvent>,
+    mut save_data: ResMut<Persistent<SaveData>>,
+    sound_assets: Res<SoundAssets>,
+    assets: Res<CoreAssets>,
+) {
+    let Ok((entity, player)) = player.get_single() else { return };
+
+    for hit in hit_reader.read() {
+        if hit.damage == 0 {
+            continue;
+        }
+        save_data.battery = save_data.battery.saturating_sub(hit.damage);
+
+        cmd.entity(entity)
+            .try_insert(PlayerFlash(Timer::from_seconds(
+                0.3,
+                TimerMode::Once,
+            )));
+        spawn_damage_text(
+            &mut cmd,
+            &assets,
+            format!("-{}", hit.damage),
+            enemy_color(&hit.elem).lighter(0.1),
+            player.pos,
+        );
+        cmd.spawn(AudioBundle {
+            source: sound_assets.attack.clone(),
+            settings: PlaybackSettings::DESPAWN,
+        });
+    }
+}
+
+fn check_player(
+    save_data: Res<Persistent<SaveData>>,
+    balance: Res<Balance>,
+    mut status_writer: EventWriter<StatusEvent>,
+) {
+    if save_data.battery < balance.max_battery(save_data.battery_level) / 8 {
         status_writer.send(StatusEvent(Status::BatteryLow));
     }
     if save_data.battery == 0 {
@@ -217,6 +327,26 @@ fn tick_wrong_move(
     }
 }
 
+fn player_flash(
+    mut cmd: Commands,
+    mut player: Query<(Entity, &mut Sprite, &mut PlayerFlash)>,
+    time: Res<Time>,
+) {
+    for (entity, mut sprite, mut flash) in player.iter_mut() {
+        let timer = flash.0.tick(time.delta());
+        if timer.just_finished() {
+            cmd.entity(entity).remove::<PlayerFlash>();
+            sprite.color = WHITE.into();
+            continue;
+        }
+        let n = (timer.fraction() * 6.) as u32;
+        sprite.color = match n % 2 {
+            0 => RED.into(),
+            _ => WHITE.into(),
+        };
+    }
+}
+
 fn on_status(
     mut player: Query<(&mut Sprite, Option<&WrongMove>), With<Player>>,
     mut status_reader: EventReader<StatusEvent>,
@@ -245,3 +375,53 @@ fn on_status(
         sprite.color = BLUE.into();
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{misc::vec_to_dir, testing::TestApp, tilemap::Tile, PlayState};
+
+    #[test]
+    fn ladder_up_goes_to_shop() {
+        let mut game = TestApp::new();
+        game.start_level();
+        let start = game.player_pos();
+        assert_eq!(
+            game.tilemap().tile(start),
+            Some(Tile::LadderUp)
+        );
+
+        let dir = game.free_direction();
+        game.step(dir);
+        assert_ne!(game.player_pos(), start);
+        assert_eq!(
+            game.state::<PlayState>(),
+            Some(&PlayState::Play)
+        );
+
+        let back = vec_to_dir(start - game.player_pos()).unwrap();
+        game.step(back);
+        assert_eq!(game.player_pos(), start);
+        assert_eq!(
+            game.state::<PlayState>(),
+            Some(&PlayState::ToShop)
+        );
+    }
+
+    #[test]
+    fn empty_battery_is_game_over() {
+        let mut game = TestApp::new();
+        game.start_level();
+        game.save_data_mut().battery = 1;
+        game.save_data_mut().money = 10;
+
+        let dir = game.free_direction();
+        game.step(dir);
+        assert_eq!(game.save_data().battery, 0);
+        assert_eq!(
+            game.state::<PlayState>(),
+            Some(&PlayState::GameOver)
+        );
+        assert_eq!(game.save_data().money, 5);
+        assert_eq!(game.save_data().deaths, 1);
+    }
+}
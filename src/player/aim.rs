@@ -0,0 +1,280 @@
+//! Area attack submodule
+//! Elemental attacks can also hit a group of tiles instead of a single enemy.
+//! Pressing `Action::Aim` starts aiming with the selected element, moving
+//! points the attack and pressing it again fires, which takes the turn
+//! Aiming only happens with the input, the attack itself is a `Command`
+//!
+//! - Fire: a cone in front of the player
+//! - Water: a line that pushes enemies back
+//! - Grass: a ring around the player that roots
+//!
+//! Selecting the basic attack stops aiming
+
+use bevy::prelude::*;
+
+use crate::{
+    assets::CoreAssets,
+    data::{Persistent, SaveData},
+    enemy::{chart::charges, enemy_color, spawn_damage_text, DamageEvent, Element, Enemy},
+    input::{Action, ActionState, MoveBuffer},
+    misc::{dir_to_vec, Direction, MoveTo},
+    player::{Command, CommandEvent, NextCommand, Player},
+    tilemap::{tile_to_pos, FieldOfView, Tile, Tilemap, TILE_SEP},
+    GameState, TurnState, SCALE,
+};
+
+const CONE_LENGTH: i32 = 3;
+const LINE_LENGTH: i32 = 4;
+
+// ·········
+// Resources
+// ·········
+
+/// Direction of the attack being aimed, `None` when not aiming
+#[derive(Resource, Default)]
+pub struct Aim(pub Option<Direction>);
+
+// ··········
+// Components
+// ··········
+
+/// Preview of a tile that the attack would hit
+#[derive(Component)]
+pub(crate) struct Highlight;
+
+/// Cost and strength of the area attack of an element
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AreaAttack {
+    /// Charges spent once when the attack is fired
+    pub charges: u32,
+    /// Scales the attack power for each enemy hit
+    pub power: f32,
+}
+
+impl AreaAttack {
+    pub fn new(elem: Element) -> Option<Self> {
+        match elem {
+            Element::Basic => None,
+            Element::Fire => Some(Self {
+                charges: 2,
+                power: 0.75,
+            }),
+            Element::Water => Some(Self {
+                charges: 1,
+                power: 0.5,
+            }),
+            Element::Grass => Some(Self {
+                charges: 2,
+                power: 0.5,
+            }),
+        }
+    }
+}
+
+// ·······
+// Systems
+// ·······
+
+pub(crate) fn init(mut cmd: Commands) {
+    cmd.insert_resource(Aim::default());
+}
+
+/// Starts and points area attacks, pressing aim again turns it into a command
+pub(crate) fn aim_attack(
+    mut cmd: Commands,
+    player: Query<&Player, Without<MoveTo>>,
+    input: Query<&ActionState<Action>>,
+    mut buffer: ResMut<MoveBuffer>,
+    mut aim: ResMut<Aim>,
+    assets: Res<CoreAssets>,
+    mut save_data: ResMut<Persistent<SaveData>>,
+    mut next: ResMut<NextCommand>,
+) {
+    if save_data.battery == 0 || next.0.is_some() {
+        return;
+    }
+    let Ok(player) = player.get_single() else { return };
+    let Ok(input) = input.get_single() else { return };
+
+    let elem = save_data.attack_selected;
+    let Some(area) = AreaAttack::new(elem) else {
+        if aim.0.is_some() {
+            aim.0 = None;
+        }
+        return;
+    };
+
+    let Some(dir) = aim.0 else {
+        // Moving this frame already took the turn
+        if !input.just_pressed(&Action::Aim) || input.pressed(&Action::Move) {
+            return;
+        }
+        let uses = charges(&mut save_data, elem).map_or(0, |uses| *uses);
+        if uses < area.charges {
+            no_charges(&mut cmd, &assets, elem, player.pos);
+            return;
+        }
+        aim.0 = Some(Direction::North);
+        return;
+    };
+
+    if let Some(dir) = buffer.queued.take() {
+        aim.0 = Some(dir);
+        return;
+    }
+
+    if input.just_pressed(&Action::Aim) {
+        next.0 = Some(Command::Area(dir, elem));
+        aim.0 = None;
+    }
+}
+
+/// Fires an area attack, spending its charges once no matter how many enemies
+/// it hits
+/// If the charges ran out since it was aimed, it shows the same "X" as aiming
+/// and the turn isn't taken
+pub(crate) fn fire_area(
+    mut cmd: Commands,
+    player: Query<(Entity, &Player), Without<MoveTo>>,
+    enemies: Query<(), With<Enemy>>,
+    assets: Res<CoreAssets>,
+    mut next: ResMut<NextCommand>,
+    tilemap: Res<Tilemap>,
+    fov: Res<FieldOfView>,
+    mut save_data: ResMut<Persistent<SaveData>>,
+    mut next_turn_state: ResMut<NextState<TurnState>>,
+    mut damage_writer: EventWriter<DamageEvent>,
+    mut command_writer: EventWriter<CommandEvent>,
+) {
+    if save_data.battery == 0 {
+        return;
+    }
+    let Ok((entity, player)) = player.get_single() else { return };
+    let Some(command @ Command::Area(dir, elem)) = next.0 else { return };
+    next.0 = None;
+    let Some(area) = AreaAttack::new(elem) else { return };
+    let Some(uses) = charges(&mut save_data, elem) else { return };
+    if *uses < area.charges {
+        no_charges(&mut cmd, &assets, elem, player.pos);
+        return;
+    }
+    *uses -= area.charges;
+    save_data.battery -= 1;
+    command_writer.send(CommandEvent(command));
+
+    // Farthest enemies first, so the ones in front can be pushed into their place
+    let mut targets: Vec<_> = area_tiles(elem, player.pos, dir, &tilemap, &fov)
+        .into_iter()
+        .filter_map(|pos| {
+            let enemy = tilemap.occupant(pos).filter(|e| enemies.contains(*e))?;
+            Some((
+                (pos - player.pos).abs().element_sum(),
+                enemy,
+            ))
+        })
+        .collect();
+    targets.sort_by_key(|(dist, _)| std::cmp::Reverse(*dist));
+
+    let push = (elem == Element::Water).then(|| dir_to_vec(&dir, 1.).as_ivec2());
+    for (_, enemy) in targets {
+        damage_writer.send(DamageEvent {
+            enemy,
+            elem,
+            power: area.power,
+            paid: true,
+            push,
+        });
+    }
+
+    cmd.entity(entity).insert(MoveTo::new(
+        tile_to_pos(player.pos),
+        tile_to_pos(player.pos),
+        (elem != Element::Grass).then_some(dir),
+    ));
+    next_turn_state.set(TurnState::Enemy);
+}
+
+/// Shows the tiles that the attack being aimed would hit
+pub(crate) fn update_highlight(
+    mut cmd: Commands,
+    highlights: Query<Entity, With<Highlight>>,
+    player: Query<&Player>,
+    aim: Res<Aim>,
+    tilemap: Res<Tilemap>,
+    fov: Res<FieldOfView>,
+    save_data: Res<Persistent<SaveData>>,
+) {
+    if !aim.is_changed() && !save_data.is_changed() {
+        return;
+    }
+    for entity in highlights.iter() {
+        cmd.entity(entity).despawn();
+    }
+
+    let Some(dir) = aim.0 else { return };
+    let Ok(player) = player.get_single() else { return };
+    let elem = save_data.attack_selected;
+
+    for pos in area_tiles(elem, player.pos, dir, &tilemap, &fov) {
+        cmd.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: enemy_color(&elem).with_alpha(0.4),
+                    custom_size: Some(Vec2::splat(TILE_SEP * SCALE)),
+                    ..default()
+                },
+                transform: Transform::from_translation(tile_to_pos(pos).extend(2.)),
+                ..default()
+            },
+            Highlight,
+            StateScoped(GameState::Play),
+        ));
+    }
+}
+
+// ·······
+// Helpers
+// ·······
+
+/// Marks the player with an "X" when there aren't enough charges for an attack
+fn no_charges(cmd: &mut Commands, assets: &CoreAssets, elem: Element, pos: IVec2) {
+    spawn_damage_text(
+        cmd,
+        assets,
+        "X".into(),
+        enemy_color(&elem).lighter(0.1),
+        pos,
+    );
+}
+
+/// Tiles hit by the area attack of an element
+/// Only tiles that the player can see are included
+pub fn area_tiles(
+    elem: Element,
+    origin: IVec2,
+    dir: Direction,
+    tilemap: &Tilemap,
+    fov: &FieldOfView,
+) -> Vec<IVec2> {
+    let open = |pos: IVec2| {
+        fov.visible.contains(&pos) && tilemap.tile(pos).is_some_and(|tile| tile != Tile::Wall)
+    };
+    let forward = dir_to_vec(&dir, 1.).as_ivec2();
+    let side = IVec2::new(forward.y, forward.x);
+
+    match elem {
+        Element::Basic => Vec::new(),
+        Element::Fire => (1..=CONE_LENGTH)
+            .flat_map(|d| (1 - d..d).map(move |s| origin + forward * d + side * s))
+            .filter(|pos| open(*pos))
+            .collect(),
+        Element::Water => (1..=LINE_LENGTH)
+            .map(|d| origin + forward * d)
+            .take_while(|pos| open(*pos))
+            .collect(),
+        Element::Grass => (-1..=1)
+            .flat_map(|y| (-1..=1).map(move |x| origin + IVec2::new(x, y)))
+            .filter(|pos| *pos != origin && open(*pos))
+            .collect(),
+    }
+}
@@ -0,0 +1,352 @@
+//! Replay module
+//! Every level is recorded as the save data it started with and the commands
+//! the player took. Random events use the `GameRng`, seeded from the save
+//! data, so carrying out the same commands plays the level in the same way
+//! Recordings are written to the `replays` data folder when the level ends,
+//! unless `AppConfig` disables them, and can be watched again with
+//! `--replay <path>`. Watching a replay replaces the save data with the one it
+//! started with, so the game uses `REPLAY_DATA_DIR` instead of the usual data
+//! folder and the real saves are never touched
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data::{self, now, Persistent, SaveData},
+    misc::MoveTo,
+    player::{Command, CommandEvent, NextCommand, Player},
+    tilemap, AppConfig, GameState, PlaySet, PlayState, TurnState,
+};
+
+/// Data folder used while watching a replay
+pub const REPLAY_DATA_DIR: &str = ".data/replay";
+
+// ······
+// Plugin
+// ······
+
+/// Replays
+/// Records the commands of the player and feeds them back
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Replayer>()
+            .add_systems(Startup, load_replay)
+            .add_systems(
+                OnEnter(GameState::Play),
+                (
+                    start_replay.before(tilemap::init),
+                    start_recording,
+                ),
+            )
+            .add_systems(OnExit(GameState::Play), save_replay)
+            .add_systems(OnEnter(TurnState::Player), settle)
+            .add_systems(OnEnter(PlayState::ToShop), settle)
+            .add_systems(OnEnter(PlayState::ToLevel), settle)
+            .add_systems(
+                OnEnter(PlayState::GameOver),
+                settle.before(data::lose_run),
+            )
+            .add_systems(OnEnter(PlayState::GameWon), settle)
+            .add_systems(
+                Update,
+                (
+                    record_commands.run_if(on_event::<CommandEvent>()),
+                    feed_command
+                        .in_set(PlaySet::Tick)
+                        .run_if(in_state(TurnState::Player)),
+                    feed_ladder
+                        .run_if(in_state(PlayState::ToShop).or_else(in_state(PlayState::ToLevel))),
+                ),
+            );
+    }
+}
+
+// ·········
+// Resources
+// ·········
+
+/// Everything needed to play a level again
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Replay {
+    /// Version of the game that recorded it, other versions may play it
+    /// differently
+    pub version: String,
+    pub start: SaveData,
+    pub commands: Vec<Command>,
+    /// Save data after the last command, used to check the playback
+    pub end: Option<SaveData>,
+}
+
+impl Replay {
+    fn new(start: SaveData) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").into(),
+            start,
+            commands: Vec::new(),
+            end: None,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub enum Replayer {
+    #[default]
+    Off,
+    Recording(Replay),
+    /// Feeding the commands of a replay, `next` is the index of the next one
+    Playing {
+        replay: Replay,
+        next: usize,
+    },
+}
+
+// ·······
+// Systems
+// ·······
+
+/// Loads the replay passed with `--replay <path>`
+fn load_replay(mut replayer: ResMut<Replayer>, config: Res<AppConfig>) {
+    let Some(path) = replay_arg() else { return };
+    if config.data_dir != REPLAY_DATA_DIR {
+        error!(
+            "not playing replay {}, the data folder isn't {}",
+            path, REPLAY_DATA_DIR
+        );
+        return;
+    }
+    let replay = match std::fs::read_to_string(&path) {
+        Ok(file) => ron::de::from_str::<Replay>(&file).map_err(|err| err.to_string()),
+        Err(err) => Err(err.to_string()),
+    };
+    match replay {
+        Ok(replay) => {
+            info!("playing replay {}", path);
+            if replay.version != env!("CARGO_PKG_VERSION") {
+                warn!(
+                    "replay recorded with version {}, it may play differently",
+                    replay.version
+                );
+            }
+            *replayer = Replayer::Playing { replay, next: 0 };
+        },
+        Err(err) => error!(
+            "failed to load replay {}: {}",
+            path, err
+        ),
+    }
+}
+
+/// Sets the level up as it was when the replay was recorded
+/// It runs again until the first command, since the game restarts once
+fn start_replay(
+    replayer: Res<Replayer>,
+    mut save_data: ResMut<Persistent<SaveData>>,
+    mut next_play_state: ResMut<NextState<PlayState>>,
+) {
+    let Replayer::Playing { replay, next: 0 } = replayer.as_ref() else { return };
+    **save_data = replay.start.clone();
+    next_play_state.set(PlayState::Play);
+}
+
+pub(crate) fn start_recording(
+    mut replayer: ResMut<Replayer>,
+    save_data: Res<Persistent<SaveData>>,
+    config: Res<AppConfig>,
+) {
+    if !config.record_replays || matches!(*replayer, Replayer::Playing { .. }) {
+        return;
+    }
+    *replayer = Replayer::Recording(Replay::new(SaveData::clone(&save_data)));
+}
+
+fn record_commands(mut replayer: ResMut<Replayer>, mut command_reader: EventReader<CommandEvent>) {
+    let Replayer::Recording(replay) = replayer.as_mut() else {
+        command_reader.clear();
+        return;
+    };
+    replay
+        .commands
+        .extend(command_reader.read().map(|event| event.0));
+}
+
+/// Nothing is moving until the player acts again
+/// Recordings keep the save data as the end, and playbacks that ran out of
+/// commands check that they ended in the same place
+fn settle(mut replayer: ResMut<Replayer>, save_data: Res<Persistent<SaveData>>) {
+    match replayer.as_mut() {
+        Replayer::Off => {},
+        Replayer::Recording(replay) => replay.end = Some(SaveData::clone(&save_data)),
+        Replayer::Playing { replay, next } => {
+            if *next < replay.commands.len() {
+                return;
+            }
+            match &replay.end {
+                Some(end) if same_outcome(end, &save_data) => info!("replay finished"),
+                Some(_) => warn!("replay finished in a different state than recorded"),
+                None => info!("replay finished, it has no end state to check"),
+            }
+            *replayer = Replayer::Off;
+        },
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_replay(mut replayer: ResMut<Replayer>, config: Res<AppConfig>) {
+    let Replayer::Recording(replay) = std::mem::take(replayer.as_mut()) else {
+        *replayer = Replayer::Off;
+        return;
+    };
+    if replay.commands.is_empty() {
+        return;
+    }
+
+    let dir = std::path::Path::new(config.data_dir).join("replays");
+    let path = dir.join(format!(
+        "{}-{}.replay.ron",
+        now(),
+        replay.start.level
+    ));
+    let file = match ron::ser::to_string_pretty(&replay, default()) {
+        Ok(file) => file,
+        Err(err) => {
+            error!("failed to serialize replay: {}", err);
+            return;
+        },
+    };
+    match std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, file)) {
+        Ok(_) => info!("replay saved to {:?}", path),
+        Err(err) => error!(
+            "failed to save replay {:?}: {}",
+            path, err
+        ),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn save_replay(mut replayer: ResMut<Replayer>) {
+    *replayer = Replayer::Off;
+}
+
+/// Gives the player the next command of the replay
+fn feed_command(
+    mut replayer: ResMut<Replayer>,
+    player: Query<(), (With<Player>, Without<MoveTo>)>,
+    mut next_command: ResMut<NextCommand>,
+) {
+    let Replayer::Playing { replay, next } = replayer.as_mut() else { return };
+    if next_command.0.is_some() || player.is_empty() {
+        return;
+    }
+    if let Some(command) = replay.commands.get(*next) {
+        next_command.0 = Some(*command);
+        *next += 1;
+    }
+}
+
+/// Answers the ladder confirmation, only undoing or going back can be recorded
+/// since leaving the level ends the replay
+fn feed_ladder(
+    mut replayer: ResMut<Replayer>,
+    mut next_command: ResMut<NextCommand>,
+    mut next_play_state: ResMut<NextState<PlayState>>,
+) {
+    let Replayer::Playing { replay, next } = replayer.as_mut() else { return };
+    if next_command.0.is_some() {
+        return;
+    }
+    match replay.commands.get(*next) {
+        Some(Command::Undo) => {
+            next_command.0 = Some(Command::Undo);
+            *next += 1;
+        },
+        Some(_) => next_play_state.set(PlayState::Play),
+        None => {},
+    }
+}
+
+// ·······
+// Helpers
+// ·······
+
+/// Path passed with `--replay <path>`, if any
+pub fn replay_arg() -> Option<String> {
+    std::env::args().skip_while(|arg| arg != "--replay").nth(1)
+}
+
+/// Whether a replay is feeding the commands instead of the input
+pub fn is_playing(replayer: Res<Replayer>) -> bool {
+    matches!(*replayer, Replayer::Playing { .. })
+}
+
+/// Compares what the commands can change
+/// The end is kept before losing a run halves the money
+fn same_outcome(a: &SaveData, b: &SaveData) -> bool {
+    a.level == b.level
+        && a.battery == b.battery
+        && a.money == b.money
+        && a.enemies_killed == b.enemies_killed
+        && a.fire_uses == b.fire_uses
+        && a.water_uses == b.water_uses
+        && a.grass_uses == b.grass_uses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestApp;
+
+    /// Starts a new level, with the replayer set up by `setup` once the old one
+    /// is gone
+    fn new_level(game: &mut TestApp, setup: impl FnOnce(&mut World)) {
+        game.set_state(GameState::Reload);
+        setup(game.app.world_mut());
+        game.run_until("the level to start", |world| {
+            world.resource::<State<GameState>>().get() == &GameState::Play
+        });
+        game.start_level();
+    }
+
+    #[test]
+    fn replays_play_the_same_level() {
+        let mut game = TestApp::new();
+        new_level(&mut game, |world| {
+            world.resource_mut::<AppConfig>().record_replays = true;
+        });
+        for i in 0..8 {
+            if i == 5 {
+                game.act(Command::Undo);
+                continue;
+            }
+            let dir = game.free_direction();
+            game.step(dir);
+        }
+        let replayer = std::mem::take(&mut *game.app.world_mut().resource_mut::<Replayer>());
+        let Replayer::Recording(replay) = replayer else {
+            panic!("the level should be recorded");
+        };
+        assert_eq!(replay.commands.len(), 8);
+        let end = replay.end.clone().expect("the replay should have an end");
+        let recorded = (game.player_pos(), game.enemies());
+
+        let mut game = TestApp::new();
+        new_level(&mut game, |world| {
+            *world.resource_mut::<Replayer>() = Replayer::Playing {
+                replay: replay.clone(),
+                next: 0,
+            };
+        });
+        game.run_until("the replay to finish", |world| {
+            matches!(
+                world.resource::<Replayer>(),
+                Replayer::Off
+            )
+        });
+        assert!(same_outcome(&end, game.save_data()));
+        assert_eq!(
+            (game.player_pos(), game.enemies()),
+            recorded
+        );
+    }
+}
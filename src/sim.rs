@@ -0,0 +1,393 @@
+//! Simulation module
+//! Plays whole runs with a bot, without a window, sound or interface, and
+//! collects stats of each one to tune the balance of the game with data
+//! The bot knows the entire level, so it plays better than someone exploring
+//! Between runs it spends its money in the shop, cheapest upgrades first
+//! The enemy weights, prices, battery and attack come from `Balance`, so a
+//! simulation can try other values without changing the game
+
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+use bevy::{app::AppExit, prelude::*, time::TimeUpdateStrategy};
+use rand::{rngs::StdRng, seq::IteratorRandom, Rng, SeedableRng};
+use serde::Serialize;
+
+use crate::{
+    data::{
+        lose_run, on_restart, Balance, Persistent, PurchaseEvent, RestartEvent, SaveData, Upgrade,
+    },
+    enemy::{chart::charges, Element, ElementChart, Enemy, EnemyType, KillEvent},
+    input::MoveBuffer,
+    misc::{vec_to_dir, MoveTo},
+    player::{Command, NextCommand, Player},
+    tilemap::{self, LevelLayout, Tile, Tilemap},
+    GameState, PlaySet, PlayState, TurnState,
+};
+
+/// Time that passes every frame, long enough to skip animations
+const TIME_STEP: Duration = Duration::from_millis(100);
+/// Turns before the bot gives up on a run
+const MAX_TURNS: u32 = 5000;
+
+const ENEMY_TYPES: [EnemyType; 8] = [
+    EnemyType::Chicken,
+    EnemyType::Cat,
+    EnemyType::Dog,
+    EnemyType::YoungOld,
+    EnemyType::Man,
+    EnemyType::Money,
+    EnemyType::Battery,
+    EnemyType::EndGame,
+];
+const UPGRADES: [Upgrade; 6] = [
+    Upgrade::Battery,
+    Upgrade::Basic,
+    Upgrade::Range,
+    Upgrade::Fire,
+    Upgrade::Water,
+    Upgrade::Grass,
+];
+
+// ······
+// Plugin
+// ······
+
+/// Simulation
+/// Needs `LogicPlugin` and `HeadlessAssetPlugin`, and it exits the app after
+/// writing the stats of every run
+pub struct SimPlugin {
+    pub runs: usize,
+    /// Seeds every run, so the same simulation can be repeated
+    pub seed: u64,
+    pub bot: Bot,
+    pub format: StatsFormat,
+    /// File for the stats, they are printed if there is none
+    pub out: Option<PathBuf>,
+    /// Replaces the balance of the game for every run
+    pub balance: Balance,
+}
+
+impl Plugin for SimPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Sim {
+            runs: self.runs,
+            bot: self.bot,
+            format: self.format,
+            out: self.out.clone(),
+            rng: StdRng::seed_from_u64(self.seed),
+            seed: 0,
+            current: RunStats::default(),
+            finished: Vec::new(),
+        })
+        .insert_resource(self.balance.clone())
+        .insert_resource(TimeUpdateStrategy::ManualDuration(TIME_STEP))
+        // The input systems run without any input
+        .init_resource::<MoveBuffer>()
+        .add_systems(
+            OnEnter(GameState::Play),
+            seed_run.before(tilemap::init),
+        )
+        .add_systems(
+            OnEnter(PlayState::GameOver),
+            end_run.after(lose_run),
+        )
+        .add_systems(OnEnter(PlayState::GameWon), end_run)
+        .add_systems(OnEnter(GameState::Shop), go_shopping)
+        .add_systems(
+            Update,
+            (
+                keep_seed
+                    .after(on_restart)
+                    .run_if(on_event::<RestartEvent>()),
+                play_turn
+                    .in_set(PlaySet::Tick)
+                    .run_if(in_state(TurnState::Player)),
+                back_to_play.run_if(in_state(PlayState::Menu).or_else(in_state(PlayState::ToShop))),
+                go_down.run_if(in_state(PlayState::ToLevel)),
+                count_kills.run_if(on_event::<KillEvent>()),
+                mute,
+            ),
+        );
+    }
+}
+
+/// How the bot chooses its moves
+#[derive(Clone, Copy, Debug)]
+pub enum Bot {
+    /// Attacks anything next to it, otherwise walks to the exit
+    Greedy,
+    /// Steps in a random direction
+    Random,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum StatsFormat {
+    Csv,
+    Json,
+}
+
+// ·········
+// Resources
+// ·········
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub enum RunOutcome {
+    #[default]
+    Died,
+    Won,
+    /// The bot took too many turns
+    GaveUp,
+}
+
+/// What happened in a run
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct RunStats {
+    pub run: usize,
+    pub outcome: RunOutcome,
+    /// Deepest level reached
+    pub depth: u32,
+    pub turns: u32,
+    pub money_earned: u32,
+    /// Battery left when the run ended
+    pub battery: u32,
+    pub kills: HashMap<EnemyType, u32>,
+    /// Upgrades bought after the run
+    pub purchases: Vec<Upgrade>,
+}
+
+#[derive(Resource)]
+struct Sim {
+    runs: usize,
+    bot: Bot,
+    format: StatsFormat,
+    out: Option<PathBuf>,
+    rng: StdRng,
+    /// Seed of the current run
+    seed: u64,
+    current: RunStats,
+    finished: Vec<RunStats>,
+}
+
+// ·······
+// Systems
+// ·······
+
+/// Replaces the random seed of a new run
+fn seed_run(mut sim: ResMut<Sim>, mut save_data: ResMut<Persistent<SaveData>>) {
+    if save_data.level == 0 {
+        sim.seed = sim.rng.gen();
+        save_data.seed = sim.seed;
+    }
+}
+
+/// Restarting picks a random seed, even if the level was already created
+fn keep_seed(sim: Res<Sim>, mut save_data: ResMut<Persistent<SaveData>>) {
+    save_data.seed = sim.seed;
+}
+
+fn play_turn(
+    player: Query<&Player, Without<MoveTo>>,
+    enemies: Query<&Enemy>,
+    tilemap: Res<Tilemap>,
+    layout: Res<LevelLayout>,
+    chart: Res<ElementChart>,
+    mut save_data: ResMut<Persistent<SaveData>>,
+    mut next: ResMut<NextCommand>,
+    mut sim: ResMut<Sim>,
+) {
+    if next.0.is_some() || save_data.battery == 0 {
+        return;
+    }
+    let Ok(player) = player.get_single() else { return };
+
+    sim.current.turns += 1;
+    if sim.current.turns > MAX_TURNS {
+        give_up(&mut sim, &mut save_data);
+        return;
+    }
+
+    let bot = sim.bot;
+    let enemy_at = |pos| tilemap.occupant(pos).and_then(|e| enemies.get(e).ok());
+    let target = match bot {
+        Bot::Greedy => tilemap
+            .neighbours(player.pos)
+            .map(|(pos, _)| pos)
+            .find(|pos| enemy_at(*pos).is_some())
+            .or_else(|| tilemap.next_step(player.pos, layout.exit)),
+        Bot::Random => tilemap
+            .neighbours(player.pos)
+            .filter(|(pos, cell)| cell.tile != Tile::LadderUp && tilemap.cost(*pos).is_some())
+            .map(|(pos, _)| pos)
+            .choose(&mut sim.rng),
+    };
+    let dir = target.and_then(|pos| vec_to_dir(pos - player.pos));
+    let (Some(target), Some(dir)) = (target, dir) else {
+        // Nowhere to go
+        give_up(&mut sim, &mut save_data);
+        return;
+    };
+
+    let elem = match enemy_at(target) {
+        Some(enemy) => best_element(&chart, enemy.elem, &save_data),
+        None => Element::Basic,
+    };
+    next.0 = Some(Command::Move(dir, elem));
+}
+
+/// Ends the run by draining the battery
+fn give_up(sim: &mut Sim, save_data: &mut SaveData) {
+    sim.current.outcome = RunOutcome::GaveUp;
+    save_data.battery = 0;
+}
+
+/// Leaves the menu, and always stays in the level instead of going to the shop
+fn back_to_play(mut next_play_state: ResMut<NextState<PlayState>>) {
+    next_play_state.set(PlayState::Play);
+}
+
+fn go_down(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::LevelTransition);
+}
+
+fn count_kills(mut sim: ResMut<Sim>, mut kill_reader: EventReader<KillEvent>) {
+    for kill in kill_reader.read() {
+        *sim.current.kills.entry(kill.typ).or_default() += 1;
+        sim.current.money_earned += kill.money;
+    }
+}
+
+/// Sounds never play, so they are removed instead
+fn mute(mut cmd: Commands, sounds: Query<Entity, With<PlaybackSettings>>) {
+    for entity in sounds.iter() {
+        cmd.entity(entity).despawn();
+    }
+}
+
+/// Saves the stats of the run, and stops once there are enough
+fn end_run(
+    mut sim: ResMut<Sim>,
+    save_data: Res<Persistent<SaveData>>,
+    play_state: Res<State<PlayState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut exit_writer: EventWriter<AppExit>,
+) {
+    let mut stats = std::mem::take(&mut sim.current);
+    stats.run = sim.finished.len();
+    stats.depth = save_data.level;
+    stats.battery = save_data.battery;
+
+    // Winning starts a new game from scratch
+    if let PlayState::GameWon = play_state.get() {
+        stats.outcome = RunOutcome::Won;
+        next_state.set(GameState::End);
+    } else {
+        next_state.set(GameState::Shop);
+    }
+
+    eprintln!(
+        "run {}: {:?} at depth {} after {} turns",
+        stats.run, stats.outcome, stats.depth, stats.turns
+    );
+    sim.finished.push(stats);
+    if sim.finished.len() < sim.runs {
+        return;
+    }
+
+    if let Err(err) = write_stats(&sim) {
+        eprintln!("failed to write the stats: {}", err);
+    }
+    exit_writer.send(AppExit::Success);
+}
+
+/// Buys the cheapest upgrade until there is no money left for any, and
+/// starts the next run
+fn go_shopping(
+    mut sim: ResMut<Sim>,
+    mut save_data: ResMut<Persistent<SaveData>>,
+    balance: Res<Balance>,
+    mut restart_writer: EventWriter<RestartEvent>,
+    mut purchase_writer: EventWriter<PurchaseEvent>,
+) {
+    let mut purchases = Vec::new();
+    while let Some(upgrade) = UPGRADES
+        .into_iter()
+        .filter(|upgrade| {
+            save_data
+                .price(*upgrade, &balance)
+                .is_some_and(|price| price <= save_data.money)
+        })
+        .min_by_key(|upgrade| save_data.price(*upgrade, &balance))
+    {
+        save_data.buy(upgrade, &balance);
+        purchase_writer.send(PurchaseEvent(upgrade));
+        purchases.push(upgrade);
+    }
+    if let Some(stats) = sim.finished.last_mut() {
+        stats.purchases = purchases;
+    }
+    restart_writer.send(RestartEvent);
+}
+
+// ·······
+// Helpers
+// ·······
+
+/// The element that does the most damage, basic attacks if it is a tie
+fn best_element(chart: &ElementChart, defense: Element, save_data: &SaveData) -> Element {
+    let mut save_data = save_data.clone();
+    let mut score = |elem| {
+        let uses = charges(&mut save_data, elem).map(|uses| *uses);
+        let outcome = chart.outcome(elem, defense, 1., uses);
+        outcome.damage - outcome.backfire as f32
+    };
+    let mut best = (Element::Basic, score(Element::Basic));
+    for elem in [Element::Fire, Element::Water, Element::Grass] {
+        let value = score(elem);
+        if value > best.1 {
+            best = (elem, value);
+        }
+    }
+    best.0
+}
+
+fn write_stats(sim: &Sim) -> std::io::Result<()> {
+    let stats = match sim.format {
+        StatsFormat::Csv => to_csv(&sim.finished),
+        StatsFormat::Json => serde_json::to_string_pretty(&sim.finished)?,
+    };
+    match &sim.out {
+        Some(path) => std::fs::write(path, stats),
+        None => {
+            println!("{}", stats);
+            Ok(())
+        },
+    }
+}
+
+fn to_csv(runs: &[RunStats]) -> String {
+    let kills = ENEMY_TYPES.map(|typ| format!("kills_{:?}", typ).to_lowercase());
+    let mut csv = format!(
+        "run,outcome,depth,turns,money_earned,battery,{},purchases\n",
+        kills.join(",")
+    );
+    for run in runs {
+        let kills = ENEMY_TYPES.map(|typ| run.kills.get(&typ).copied().unwrap_or(0).to_string());
+        let purchases: Vec<_> = run
+            .purchases
+            .iter()
+            .map(|upgrade| format!("{:?}", upgrade))
+            .collect();
+        csv += &format!(
+            "{},{:?},{},{},{},{},{},{}\n",
+            run.run,
+            run.outcome,
+            run.depth,
+            run.turns,
+            run.money_earned,
+            run.battery,
+            kills.join(","),
+            purchases.join(";"),
+        );
+    }
+    csv
+}
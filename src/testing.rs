@@ -0,0 +1,210 @@
+//! Test support module
+//! Builds the game without a window, sound or interface so tests can play it
+//! frame by frame, giving the player commands and checking the states
+//! Undo is included, since it is a command that replays can contain
+//! Assets are stubs from `HeadlessAssetPlugin`, only levels and game data are
+//! loaded, and every app saves its data in a temporary folder of its own
+
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use bevy::{
+    prelude::*,
+    state::{app::StatesPlugin, state::FreelyMutableState},
+    time::TimeUpdateStrategy,
+};
+
+use crate::{
+    assets::HeadlessAssetPlugin,
+    data::{Persistent, SaveData},
+    enemy::{Element, Enemy},
+    input::MoveBuffer,
+    misc::{dir_to_vec, Direction, MoveTo},
+    player::{Command, NextCommand, Player},
+    tilemap::{Tile, Tilemap},
+    undo::UndoPlugin,
+    AppConfig, GameState, LogicPlugin, PlayState, TurnState,
+};
+
+/// Time that passes every frame, long enough to skip animations
+const TIME_STEP: Duration = Duration::from_millis(100);
+/// Frames to wait for something before failing the test
+const MAX_FRAMES: usize = 1000;
+
+static NEXT_APP: AtomicUsize = AtomicUsize::new(0);
+
+/// Game app driven by a test
+pub(crate) struct TestApp {
+    pub app: App,
+    data_dir: &'static str,
+}
+
+impl TestApp {
+    /// Builds the game and waits until the assets are loaded
+    pub fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!(
+            "kenney-jam-test-{}-{}",
+            std::process::id(),
+            NEXT_APP.fetch_add(1, Ordering::Relaxed)
+        ));
+        Self::launch(Box::leak(
+            dir.to_string_lossy().into_owned().into_boxed_str(),
+        ))
+    }
+
+    /// Closes the game and builds it again, keeping the data folder
+    pub fn relaunch(mut self) -> Self {
+        let data_dir = std::mem::take(&mut self.data_dir);
+        drop(self);
+        Self::launch(data_dir)
+    }
+
+    fn launch(data_dir: &'static str) -> Self {
+        let mut app = App::new();
+        app.insert_resource(AppConfig {
+            data_dir,
+            record_replays: false,
+            ..default()
+        })
+        .add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            StatesPlugin,
+            HeadlessAssetPlugin,
+            LogicPlugin,
+            // Undo is a command, so it works without input
+            UndoPlugin,
+        ))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(TIME_STEP))
+        // The input systems run without any input
+        .init_resource::<MoveBuffer>();
+
+        let mut test = Self { app, data_dir };
+        test.run_until("the game to load", |world| {
+            world.resource::<State<GameState>>().get() == &GameState::Play
+        });
+        test
+    }
+
+    /// Leaves the menu and waits for the player to act
+    pub fn start_level(&mut self) {
+        self.set_state(PlayState::Play);
+        self.wait_turn();
+    }
+
+    pub fn update(&mut self) {
+        self.app.update();
+    }
+
+    /// Updates until the condition is met, panics if it takes too long
+    pub fn run_until(&mut self, what: &str, condition: impl Fn(&World) -> bool) {
+        for _ in 0..MAX_FRAMES {
+            self.update();
+            if condition(self.app.world()) {
+                return;
+            }
+        }
+        panic!("timed out waiting for {}", what);
+    }
+
+    /// Gives the player a command and waits until they can act again, or
+    /// until the level stops being played
+    pub fn act(&mut self, command: Command) {
+        self.app.world_mut().resource_mut::<NextCommand>().0 = Some(command);
+        self.update();
+        self.wait_turn();
+    }
+
+    /// Walks in a direction, attacking with basic attacks
+    pub fn step(&mut self, dir: Direction) {
+        self.act(Command::Move(dir, Element::Basic));
+    }
+
+    /// Direction of an empty tile next to the player that isn't a ladder, so
+    /// stepping there doesn't ask to leave the level
+    pub fn free_direction(&mut self) -> Direction {
+        let pos = self.player_pos();
+        let tilemap = self.tilemap();
+        *Direction::iter()
+            .find(|dir| {
+                let next = pos + dir_to_vec(dir, 1.).as_ivec2();
+                tilemap.cost(next) == Some(1)
+                    && !matches!(
+                        tilemap.tile(next),
+                        Some(Tile::LadderUp | Tile::LadderDown)
+                    )
+            })
+            .expect("the player is surrounded")
+    }
+
+    fn wait_turn(&mut self) {
+        self.run_until("the turn of the player", |world| {
+            let waiting = world
+                .get_resource::<State<TurnState>>()
+                .is_some_and(|state| state.get() == &TurnState::Player)
+                && world.resource::<NextCommand>().0.is_none();
+            let moving = world
+                .iter_entities()
+                .any(|entity| entity.contains::<Player>() && entity.contains::<MoveTo>());
+            let playing = world
+                .get_resource::<State<PlayState>>()
+                .is_some_and(|state| state.get() == &PlayState::Play);
+            (waiting && !moving) || !playing
+        });
+    }
+
+    pub fn set_state<S: FreelyMutableState>(&mut self, state: S) {
+        self.app
+            .world_mut()
+            .resource_mut::<NextState<S>>()
+            .set(state);
+        self.update();
+    }
+
+    pub fn state<S: States>(&self) -> Option<&S> {
+        self.app.world().get_resource::<State<S>>().map(State::get)
+    }
+
+    pub fn save_data(&self) -> &SaveData {
+        self.app.world().resource::<Persistent<SaveData>>()
+    }
+
+    pub fn save_data_mut(&mut self) -> Mut<'_, Persistent<SaveData>> {
+        self.app.world_mut().resource_mut::<Persistent<SaveData>>()
+    }
+
+    pub fn player_pos(&mut self) -> IVec2 {
+        self.app
+            .world_mut()
+            .query::<&Player>()
+            .single(self.app.world())
+            .pos
+    }
+
+    pub fn tilemap(&self) -> &Tilemap {
+        self.app.world().resource::<Tilemap>()
+    }
+
+    /// Position and health of every enemy, sorted by position
+    pub fn enemies(&mut self) -> Vec<(IVec2, f32)> {
+        let world = self.app.world_mut();
+        let mut enemies: Vec<_> = world
+            .query::<&Enemy>()
+            .iter(world)
+            .map(|enemy| (enemy.pos, enemy.health))
+            .collect();
+        enemies.sort_by_key(|(pos, _)| (pos.x, pos.y));
+        enemies
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        if self.data_dir.is_empty() {
+            return;
+        }
+        let _ = std::fs::remove_dir_all(self.data_dir);
+    }
+}
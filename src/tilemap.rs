@@ -1,21 +1,33 @@
-use std::{
-    collections::{HashMap, HashSet},
-    hash::{Hash, Hasher},
-};
-
 use bevy::prelude::*;
-use itertools::Itertools;
-use rand::{seq::SliceRandom, Rng};
-
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+pub use self::{
+    authored::AuthoredLevel,
+    connectivity::LevelStats,
+    fov::{FieldOfView, Seen, Tint},
+    generator::{LevelGenerator, LevelGenerators},
+    grid::{TileCell, Tilemap},
+    layout::{EnemySpawn, LevelLayout, Room},
+    pathfinding::PlayerDistances,
+};
 use crate::{
-    assets::{SpriteAssets, ATLAS_SIZE},
-    data::{max_battery, max_range, Persistent, SaveData},
-    enemy::{enemy_color, get_enemy},
-    misc::{dir_to_vec, Direction},
-    player::{Status, StatusEvent},
-    GameState, PlayState, SCALE,
+    assets::{LevelAssets, RonAssetLoader, SpriteAssets, SpriteCatalogue},
+    data::{max_range, Balance, Persistent, SaveData},
+    enemy::{enemy_color, speed, AiState, Enemy},
+    player::{Player, Status, StatusEvent},
+    turn::Speed,
+    GameState, PlayState, TurnState, SCALE,
 };
 
+pub mod authored;
+pub mod connectivity;
+pub mod fov;
+pub mod generator;
+pub mod grid;
+pub mod layout;
+pub mod pathfinding;
+
 pub const TILE_SEP: f32 = 20.;
 pub const ROOM_SEP: UVec2 = UVec2::new(15, 11);
 
@@ -27,7 +39,29 @@ pub struct TilemapPlugin;
 
 impl Plugin for TilemapPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Play), init)
+        app.init_asset::<AuthoredLevel>()
+            .init_asset_loader::<RonAssetLoader<AuthoredLevel>>()
+            .init_resource::<LevelGenerators>()
+            .init_resource::<PlayerDistances>()
+            .init_resource::<FieldOfView>()
+            .add_systems(
+                OnEnter(GameState::Play),
+                (init, spawn_level).chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    reload_level,
+                    fov::update_fov,
+                    fov::hide_enemies,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Play)),
+            )
+            .add_systems(
+                OnEnter(TurnState::Enemy),
+                pathfinding::update_distances,
+            )
             .add_systems(
                 OnEnter(GameState::LevelTransition),
                 level_transition,
@@ -36,60 +70,16 @@ impl Plugin for TilemapPlugin {
     }
 }
 
-// ·········
-// Resources
-// ·········
-
-#[derive(Resource)]
-pub struct Tilemap {
-    tiles: HashSet<TileData>,
-}
-
-impl Tilemap {
-    pub fn get_tile(&self, pos: IVec2) -> Option<Entity> {
-        self.tiles.get(&TileData::pos(pos)).map(|t| t.entity)
-    }
-}
-
-struct TileData {
-    pub x: i32,
-    pub y: i32,
-    pub entity: Entity,
-}
-
-impl TileData {
-    pub fn pos(p: IVec2) -> Self {
-        Self {
-            x: p.x,
-            y: p.y,
-            entity: Entity::PLACEHOLDER,
-        }
-    }
-}
-
-impl Hash for TileData {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.x.hash(state);
-        self.y.hash(state);
-    }
-}
-
-impl PartialEq for TileData {
-    fn eq(&self, other: &Self) -> bool {
-        self.x == other.x && self.y == other.y
-    }
-}
-
-impl Eq for TileData {}
-
 // ··········
 // Components
 // ··········
 
-#[derive(Component, Default, PartialEq, Eq, Hash, Clone)]
+#[derive(Component, Default, PartialEq, Eq, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum Tile {
     #[default]
     Ground,
+    /// Ground with an enemy in it, it is only used in layouts
+    /// Once spawned it is ground with the enemy as the occupant
     Enemy,
     Path,
     Wall,
@@ -98,24 +88,126 @@ pub enum Tile {
     Final,
 }
 
+// ·········
+// Resources
+// ·········
+
+/// Random generator for everything that happens while playing a level
+/// It is seeded like the level, so replaying the same turns gives the same
+/// results
+#[derive(Resource, Clone)]
+pub struct GameRng(pub StdRng);
+
 // ·······
 // Systems
 // ·······
 
-fn init(mut cmd: Commands, sprite_assets: Res<SpriteAssets>, save_data: Res<Persistent<SaveData>>) {
+pub(crate) fn init(
+    mut cmd: Commands,
+    save_data: Res<Persistent<SaveData>>,
+    level_assets: Res<LevelAssets>,
+    authored: Res<Assets<AuthoredLevel>>,
+    generators: Res<LevelGenerators>,
+    sprites: Res<SpriteCatalogue>,
+    balance: Res<Balance>,
+) {
     let level = save_data.level;
-    let tiles = generate_level(
+    // A different stream from the one that builds the level
+    cmd.insert_resource(GameRng(level_rng(
+        !save_data.seed,
+        level,
+    )));
+
+    // Use a hand made level if there is one for this depth
+    if let Some(authored) = authored::find_level(&level_assets.levels, &authored, level) {
+        debug!("level {} is authored", level);
+        cmd.insert_resource(authored.to_layout(save_data.seed, &sprites));
+        return;
+    }
+
+    let layout = generator::generate_level(
+        generators.get(level),
+        save_data.seed,
+        level,
+        &sprites,
+        &balance,
+    );
+
+    let stats = layout.stats();
+    debug!("level {} generated: {:?}", level, stats);
+    // Generating carves corridors until everything can be reached
+    if !stats.is_connected() {
+        error!(
+            "level {} is not fully connected after carving",
+            level
+        );
+    }
+
+    cmd.insert_resource(layout);
+}
+
+/// Creates the tile and enemy entities described by the level layout
+pub(crate) fn spawn_level(
+    mut cmd: Commands,
+    sprite_assets: Res<SpriteAssets>,
+    sprites: Res<SpriteCatalogue>,
+    layout: Res<LevelLayout>,
+) {
+    let tilemap = spawn_layout(
         &mut cmd,
         &sprite_assets,
-        level,
-        (
-            2 + (level * 0.3 as u32).clamp(0, 3),
-            5 + (level * 0.5 as u32).clamp(0, 5),
-        ),
-        (ROOM_SEP.x / 2 + 1, ROOM_SEP.x - 4),
-        (ROOM_SEP.y / 2 + 1, ROOM_SEP.y - 4),
+        &sprites,
+        &layout,
+    );
+    cmd.insert_resource(tilemap);
+}
+
+/// Rebuilds the current level when its authored file changes on disk
+/// This only happens with the `file_watcher` feature
+fn reload_level(
+    mut cmd: Commands,
+    mut asset_events: EventReader<AssetEvent<AuthoredLevel>>,
+    level_assets: Res<LevelAssets>,
+    authored: Res<Assets<AuthoredLevel>>,
+    sprite_assets: Res<SpriteAssets>,
+    sprites: Res<SpriteCatalogue>,
+    save_data: Res<Persistent<SaveData>>,
+    entities: Query<Entity, Or<(With<Tile>, With<Enemy>)>>,
+    mut player: Query<(Entity, &mut Player, &mut Transform)>,
+) {
+    let modified = asset_events
+        .read()
+        .any(|event| matches!(event, AssetEvent::Modified { .. }));
+    if !modified {
+        return;
+    }
+    let Some(authored) = authored::find_level(
+        &level_assets.levels,
+        &authored,
+        save_data.level,
+    ) else {
+        return;
+    };
+
+    info!("reloading level {}", save_data.level);
+    for entity in entities.iter() {
+        cmd.entity(entity).despawn();
+    }
+
+    let layout = authored.to_layout(save_data.seed, &sprites);
+    let mut tilemap = spawn_layout(
+        &mut cmd,
+        &sprite_assets,
+        &sprites,
+        &layout,
     );
-    cmd.insert_resource(Tilemap { tiles });
+    if let Ok((entity, mut player, mut trans)) = player.get_single_mut() {
+        player.pos = layout.ladder_up;
+        trans.translation = tile_to_pos(layout.ladder_up).extend(trans.translation.z);
+        tilemap.set_occupant(player.pos, Some(entity));
+    }
+    cmd.insert_resource(tilemap);
+    cmd.insert_resource(layout);
 }
 
 fn level_transition(
@@ -126,7 +218,10 @@ fn level_transition(
 ) {
     next_state.set(GameState::Play);
     next_play_state.set(PlayState::Play);
-    let _ = save_data.update(|data| data.level += 1);
+    let _ = save_data.update(|data| {
+        data.level += 1;
+        data.levels_completed += 1;
+    });
     if save_data.level >= max_range(save_data.range_level) {
         status_writer.send(StatusEvent(Status::ConnectionEmpty));
     } else if save_data.level + 2 >= max_range(save_data.range_level) {
@@ -138,13 +233,16 @@ fn end(
     mut next_state: ResMut<NextState<GameState>>,
     mut next_play_state: ResMut<NextState<PlayState>>,
     mut save_data: ResMut<Persistent<SaveData>>,
+    balance: Res<Balance>,
 ) {
     next_state.set(GameState::Play);
     next_play_state.set(PlayState::Menu);
     let _ = save_data.revert_to_default();
-    let battery = max_battery(save_data.battery_level);
+    let battery = balance.max_battery(save_data.battery_level);
+    let seed = rand::random();
     let _ = save_data.update(|data| {
         data.level = 0;
+        data.seed = seed;
         data.battery = battery;
         data.fire_uses = data.fire;
         data.water_uses = data.water;
@@ -168,243 +266,121 @@ pub fn tile_to_pos(pos: IVec2) -> Vec2 {
     )
 }
 
-const WALL_FIRST: [usize; 4] = [
-    ATLAS_SIZE.0 * 13,
-    ATLAS_SIZE.0 * 17 + 10,
-    ATLAS_SIZE.0 * 17 + 13,
-    ATLAS_SIZE.0 * 18 + 10,
-];
-const WALL_FINAL: [usize; 5] = [
-    ATLAS_SIZE.0 * 17 + 10,
-    ATLAS_SIZE.0 * 13,
-    ATLAS_SIZE.0 * 13 + 1,
-    ATLAS_SIZE.0 * 13 + 2,
-    ATLAS_SIZE.0 * 12 + 2,
-];
-const GROUND_FIRST: [usize; 8] = [0, 0, 1, 2, 5, 6, 7, ATLAS_SIZE.0 * 6 + 16];
-const GROUND_FINAL: [usize; 9] = [
-    0,
-    0,
-    1,
-    2,
-    3,
-    4,
-    ATLAS_SIZE.0 + 19,
-    ATLAS_SIZE.0 + 19,
-    ATLAS_SIZE.0 + 19,
-];
-
-fn tile_to_index(tile: Tile, level: u32) -> usize {
-    let mut rng = rand::thread_rng();
-    let tile = match tile {
-        Tile::Ground => {
-            if level < 5 {
-                GROUND_FIRST.choose(&mut rng).unwrap()
-            } else {
-                GROUND_FINAL.choose(&mut rng).unwrap()
-            }
-        },
-        Tile::Path => [1, 2, 3, 4].choose(&mut rng).unwrap(),
-        Tile::Wall => {
-            if level < 5 {
-                WALL_FIRST.choose(&mut rng).unwrap()
-            } else {
-                WALL_FINAL.choose(&mut rng).unwrap()
-            }
-        },
-        Tile::LadderDown => &(ATLAS_SIZE.0 * 6 + 3),
-        Tile::LadderUp => &(ATLAS_SIZE.0 * 6 + 2),
-        _ => &0,
-    };
-    *tile
+/// Creates the random generator for a level
+/// The same run seed and level always produce the same sequence, so every
+/// decision taken with it (layout, enemies, sprites) can be reproduced
+pub fn level_rng(seed: u64, level: u32) -> StdRng {
+    StdRng::seed_from_u64(seed ^ (level as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15))
 }
 
-fn generate_level(
-    cmd: &mut Commands,
-    sprite_assets: &SpriteAssets,
-    level: u32,
-    rooms: (u32, u32),
-    size_x: (u32, u32),
-    size_y: (u32, u32),
-) -> HashSet<TileData> {
-    let mut rng = rand::thread_rng();
-
-    let rooms = rng.gen_range(rooms.0..=rooms.1);
-    let mut room_indices = HashSet::new();
-    let mut room_pos = IVec2::ZERO;
-
-    let mut tiles = HashMap::new();
-
-    // Generate rooms
-    for _ in 0..rooms {
-        loop {
-            if room_indices.insert(room_pos) {
-                break;
-            };
-            let dir: Direction = rng.gen();
-            let global_offset = dir_to_vec(&dir, 1.).as_ivec2();
-            room_pos += global_offset;
-        }
-
-        let size = UVec2::new(
-            rng.gen_range(size_x.0..=size_x.1),
-            rng.gen_range(size_y.0..=size_y.1),
-        );
-
-        let offset = IVec2::new(
-            rng.gen_range(0..(ROOM_SEP.x - size.x)) as i32 + room_pos.x * ROOM_SEP.x as i32,
-            rng.gen_range(0..(ROOM_SEP.y - size.y)) as i32 + room_pos.y * ROOM_SEP.y as i32,
-        );
-
-        generate_room(&mut tiles, size, offset, level);
+/// Name of the sprite of a tile in the catalogue
+fn tile_sprite(tile: Tile, level: u32) -> &'static str {
+    match tile {
+        Tile::Ground if level < 5 => "tile.ground.shallow",
+        Tile::Ground => "tile.ground.deep",
+        Tile::Path => "tile.path",
+        Tile::Wall if level < 5 => "tile.wall.shallow",
+        Tile::Wall => "tile.wall.deep",
+        Tile::LadderDown => "tile.ladder.down",
+        Tile::LadderUp => "tile.ladder.up",
+        _ => "tile.empty",
     }
+}
 
-    // Generate corridors
-    let mut aux = room_indices.clone();
-    for a in room_indices.clone() {
-        for dir in Direction::iter() {
-            let offset = dir_to_vec(dir, 1.).as_ivec2();
-            let b = a + offset;
-            let sep = match dir {
-                Direction::North | Direction::South => ROOM_SEP.x * 2,
-                Direction::East | Direction::West => ROOM_SEP.y * 2,
-            };
-            if aux.contains(&b) {
-                // Corridor
-                let center_a = a * ROOM_SEP.as_ivec2() + ROOM_SEP.as_ivec2() / 2;
-                let mut first_wall = false;
-                for pos in 0..sep {
-                    let pos = center_a + pos as i32 * offset;
-                    let tile = TileData::pos(pos);
-                    // Find the first wall and start laying paths
-                    if !first_wall {
-                        if let Some(Tile::Wall) = tiles.get(&tile) {
-                            first_wall = true;
-                            tiles.insert(tile, Tile::Path);
-                            continue;
-                        }
-                    }
-                    // Lay paths until next wall
-                    else if let Some(Tile::Wall) = tiles.insert(tile, Tile::Path) {
-                        break;
-                    }
-                    let offset = offset.perp();
-                    let left = TileData::pos(pos + offset);
-                    tiles.entry(left).or_insert(Tile::Wall);
-                    let right = TileData::pos(pos - offset);
-                    tiles.entry(right).or_insert(Tile::Wall);
-                }
-            }
-        }
-        aux.remove(&a);
+fn spawn_layout(
+    cmd: &mut Commands,
+    sprite_assets: &SpriteAssets,
+    sprites: &SpriteCatalogue,
+    layout: &LevelLayout,
+) -> Tilemap {
+    // Sprite variations are cosmetic, but they are also seeded so that the same
+    // layout always looks the same
+    let mut rng = level_rng(layout.seed, layout.level);
+
+    let mut tilemap = Tilemap::new(layout.origin, layout.size);
+    for (pos, tile) in layout.tiles() {
+        let sprite = sprites.get(tile_sprite(*tile, layout.level));
+        let index = sprite.pick(&mut rng);
+        let tile = match tile {
+            Tile::Enemy => Tile::Ground,
+            tile => *tile,
+        };
+        tilemap.insert(pos, TileCell {
+            tile,
+            entity: spawn_tile(
+                cmd,
+                sprite_assets,
+                pos,
+                tile,
+                index,
+                sprite.tint(),
+            ),
+            occupant: None,
+        });
     }
 
-    // Insert ladder up
-    tiles.insert(
-        TileData::pos(ROOM_SEP.as_ivec2() / 2),
-        Tile::LadderUp,
-    );
-
-    // Insert ladder down or final key
-    // This iterator is supposed to be random
-    for (_, tile) in tiles.iter_mut() {
-        if !matches!(tile, Tile::Ground) {
-            continue;
-        }
-        *tile = if level < 9 { Tile::LadderDown } else { Tile::Enemy };
-        break;
+    for spawn in &layout.enemies {
+        let pos = spawn.enemy.pos;
+        let entity = spawn_enemy(cmd, sprite_assets, spawn.clone());
+        tilemap.set_occupant(pos, Some(entity));
     }
 
-    // Create actual tiles
-    let mut unique = false;
-    tiles
-        .iter()
-        .map(|(k, v)| TileData {
-            x: k.x,
-            y: k.y,
-            entity: create_tile(
-                cmd,
-                sprite_assets,
-                level,
-                &mut unique,
-                IVec2::new(k.x, k.y),
-                v.clone(),
-                tile_to_index(v.clone(), level),
-            ),
-        })
-        .collect()
+    tilemap
 }
 
-fn generate_room(tiles: &mut HashMap<TileData, Tile>, size: UVec2, offset: IVec2, level: u32) {
-    let mut rng = rand::thread_rng();
-    let grow = (level / 3, level / 2);
-    let num_enemies = rng.gen_range(1 + grow.0..3 + grow.1);
-
-    let mut indices: Vec<(u32, u32)> = (1..=size.x).cartesian_product(0..=size.y).collect();
-    indices.shuffle(&mut rand::thread_rng());
-    let enemy_tiles = indices.get(0..num_enemies as usize);
-
-    for (x, y) in (0..=size.x + 1).cartesian_product(0..=size.y + 1) {
-        let tile = if x == 0 || x == size.x + 1 || y == 0 || y == size.y + 1 {
-            Tile::Wall
-        } else if let Some(tiles) = enemy_tiles {
-            if tiles.contains(&(x, y)) {
-                Tile::Enemy
-            } else {
-                Tile::Ground
-            }
-        } else {
-            Tile::Ground
-        };
-
-        tiles.insert(
-            TileData {
-                x: x as i32 + offset.x,
-                y: y as i32 + offset.y,
-                entity: Entity::PLACEHOLDER,
+pub(crate) fn spawn_enemy(
+    cmd: &mut Commands,
+    sprite_assets: &SpriteAssets,
+    spawn: EnemySpawn,
+) -> Entity {
+    let EnemySpawn {
+        enemy,
+        behaviour,
+        sprite,
+    } = spawn;
+    let speed = (!behaviour.is_still()).then(|| Speed(speed(enemy.typ)));
+    let mut entity = cmd.spawn((
+        SpriteBundle {
+            transform: Transform::from_translation(tile_to_pos(enemy.pos).extend(5.))
+                .with_scale(Vec3::splat(SCALE)),
+            texture: sprite_assets.one_bit.clone(),
+            sprite: Sprite {
+                color: enemy_color(&enemy.elem),
+                ..default()
             },
-            tile,
-        );
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        TextureAtlas {
+            layout: sprite_assets.one_bit_atlas.clone(),
+            index: sprite,
+        },
+        enemy,
+        behaviour,
+        AiState::default(),
+        StateScoped(GameState::Play),
+    ));
+    if let Some(speed) = speed {
+        entity.insert(speed);
     }
+    entity.id()
 }
 
-fn create_tile(
+fn spawn_tile(
     cmd: &mut Commands,
     sprite_assets: &SpriteAssets,
-    level: u32,
-    unique: &mut bool,
     pos: IVec2,
     tile: Tile,
     index: usize,
+    color: Color,
 ) -> Entity {
-    // If it has an enemy, spawn it
-    if matches!(tile, Tile::Enemy) {
-        let (enemy, index) = get_enemy(pos, level, unique);
-        cmd.spawn((
-            SpriteBundle {
-                transform: Transform::from_translation(tile_to_pos(pos).extend(5.))
-                    .with_scale(Vec3::splat(SCALE)),
-                texture: sprite_assets.one_bit.clone(),
-                sprite: Sprite {
-                    color: enemy_color(&enemy.elem),
-                    ..default()
-                },
-                ..default()
-            },
-            TextureAtlas {
-                layout: sprite_assets.one_bit_atlas.clone(),
-                index,
-            },
-            enemy,
-            StateScoped(GameState::Play),
-        ));
-    }
-
     cmd.spawn((
         SpriteBundle {
             transform: Transform::from_translation(tile_to_pos(pos).extend(0.))
                 .with_scale(Vec3::splat(SCALE)),
             texture: sprite_assets.one_bit.clone(),
+            sprite: Sprite { color, ..default() },
+            visibility: Visibility::Hidden,
             ..default()
         },
         TextureAtlas {
@@ -412,6 +388,8 @@ fn create_tile(
             index,
         },
         tile,
+        Tint(color),
+        Seen::default(),
         StateScoped(GameState::Play),
     ))
     .id()
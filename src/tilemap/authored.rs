@@ -0,0 +1,254 @@
+//! Authored levels submodule
+//! Hand made levels are written as RON files with a grid of glyphs and loaded
+//! as regular assets, so they are hot reloaded while the game is running
+//!
+//! Glyphs:
+//! - `#` wall, `.` ground, `=` path, `<` ladder up, `>` ladder down
+//! - `*` the final jewel, that ends the game when it is picked up
+//! - ` ` nothing
+//!
+//! There is no glyph for `Tile::Final`. The jewel is picked up like any other
+//! pickup, by attacking it, so `*` places an `EndGame` enemy on the ground
+//! instead, the same as the generated last level
+//! - Any other character must be defined in `enemies`
+
+use std::{collections::HashMap, fmt};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    assets::{RonAsset, SpriteCatalogue},
+    enemy::{new_enemy, Element, EnemyType},
+    tilemap::{level_rng, EnemySpawn, LevelLayout, Tile},
+};
+
+// ······
+// Assets
+// ······
+
+/// A level made by hand
+/// It replaces the generated level at its depth
+#[derive(Asset, TypePath, Debug)]
+pub struct AuthoredLevel {
+    /// Depth where this level is used, starting at 0
+    pub depth: u32,
+    pub tiles: HashMap<IVec2, Tile>,
+    pub enemies: Vec<(IVec2, EnemyType, Element)>,
+    pub ladder_up: IVec2,
+    pub exit: IVec2,
+}
+
+impl AuthoredLevel {
+    /// Builds the layout for this level
+    /// The seed is only used to choose sprite variations
+    pub fn to_layout(&self, seed: u64, sprites: &SpriteCatalogue) -> LevelLayout {
+        let mut rng = level_rng(seed, self.depth);
+
+        let mut layout = LevelLayout::from_tiles(seed, self.depth, self.tiles.clone());
+        layout.ladder_up = self.ladder_up;
+        layout.exit = self.exit;
+        layout.enemies = self
+            .enemies
+            .iter()
+            .map(|&(pos, typ, elem)| {
+                let (enemy, behaviour, sprite) = new_enemy(pos, typ, elem, sprites, &mut rng);
+                EnemySpawn {
+                    enemy,
+                    behaviour,
+                    sprite,
+                }
+            })
+            .collect();
+
+        layout
+    }
+}
+
+/// The contents of a `.level.ron` file
+#[derive(Deserialize)]
+pub struct LevelFile {
+    depth: u32,
+    /// Rows of glyphs, the first one is the top of the level
+    rows: Vec<String>,
+    /// Glyphs that represent an enemy
+    #[serde(default)]
+    enemies: HashMap<char, EnemyMarker>,
+}
+
+#[derive(Deserialize)]
+struct EnemyMarker {
+    typ: EnemyType,
+    elem: Element,
+}
+
+impl RonAsset for AuthoredLevel {
+    type File = LevelFile;
+
+    const EXTENSION: &'static str = "level.ron";
+
+    fn from_file(file: LevelFile) -> Result<Self, String> {
+        parse_level(file).map_err(|err| err.to_string())
+    }
+}
+
+/// What can be wrong in the glyphs of a level
+#[derive(Debug)]
+pub enum LevelError {
+    UnknownGlyph(char, IVec2),
+    MissingLadderUp,
+    DuplicateLadderUp,
+    MissingExit,
+    DuplicateExit,
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownGlyph(glyph, pos) => {
+                write!(
+                    f,
+                    "unknown glyph '{}' at {}",
+                    glyph, pos
+                )
+            },
+            Self::MissingLadderUp => write!(f, "the level has no ladder up '<'"),
+            Self::DuplicateLadderUp => write!(
+                f,
+                "the level has more than one ladder up '<'"
+            ),
+            Self::MissingExit => write!(
+                f,
+                "the level has no ladder down '>' or jewel '*'"
+            ),
+            Self::DuplicateExit => write!(
+                f,
+                "the level has more than one ladder down '>' or jewel '*'"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+// ·······
+// Helpers
+// ·······
+
+fn parse_level(file: LevelFile) -> Result<AuthoredLevel, LevelError> {
+    let mut tiles = HashMap::new();
+    let mut enemies = Vec::new();
+    let mut ladder_up = Vec::new();
+    let mut exit = Vec::new();
+
+    let height = file.rows.len() as i32;
+    for (row, line) in file.rows.iter().enumerate() {
+        for (col, glyph) in line.chars().enumerate() {
+            let pos = IVec2::new(col as i32, height - 1 - row as i32);
+            let tile = match glyph {
+                ' ' => continue,
+                '#' => Tile::Wall,
+                '.' => Tile::Ground,
+                '=' => Tile::Path,
+                '<' => {
+                    ladder_up.push(pos);
+                    Tile::LadderUp
+                },
+                '>' => {
+                    exit.push(pos);
+                    Tile::LadderDown
+                },
+                '*' => {
+                    exit.push(pos);
+                    enemies.push((pos, EnemyType::EndGame, Element::Basic));
+                    Tile::Enemy
+                },
+                glyph => {
+                    let Some(marker) = file.enemies.get(&glyph) else {
+                        return Err(LevelError::UnknownGlyph(glyph, pos));
+                    };
+                    enemies.push((pos, marker.typ, marker.elem));
+                    Tile::Enemy
+                },
+            };
+            tiles.insert(pos, tile);
+        }
+    }
+
+    let ladder_up = match ladder_up[..] {
+        [] => return Err(LevelError::MissingLadderUp),
+        [ladder_up] => ladder_up,
+        _ => return Err(LevelError::DuplicateLadderUp),
+    };
+    let exit = match exit[..] {
+        [] => return Err(LevelError::MissingExit),
+        [exit] => exit,
+        _ => return Err(LevelError::DuplicateExit),
+    };
+
+    Ok(AuthoredLevel {
+        depth: file.depth,
+        tiles,
+        enemies,
+        ladder_up,
+        exit,
+    })
+}
+
+/// Returns the authored level for a depth, if there is one loaded
+pub fn find_level<'a>(
+    handles: &[Handle<AuthoredLevel>],
+    levels: &'a Assets<AuthoredLevel>,
+    depth: u32,
+) -> Option<&'a AuthoredLevel> {
+    handles
+        .iter()
+        .filter_map(|handle| levels.get(handle))
+        .find(|level| level.depth == depth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(rows: &[&str]) -> Result<AuthoredLevel, LevelError> {
+        parse_level(LevelFile {
+            depth: 0,
+            rows: rows.iter().map(|row| row.to_string()).collect(),
+            enemies: HashMap::new(),
+        })
+    }
+
+    #[test]
+    fn levels_need_one_ladder_up_and_one_exit() {
+        let level = parse(&["#####", "#<.*#", "#####"]).unwrap();
+        assert_eq!(level.ladder_up, IVec2::new(1, 1));
+        assert_eq!(level.exit, IVec2::new(3, 1));
+        assert_eq!(level.enemies, [(
+            level.exit,
+            EnemyType::EndGame,
+            Element::Basic
+        )]);
+
+        assert!(matches!(
+            parse(&["#..>#"]),
+            Err(LevelError::MissingLadderUp)
+        ));
+        assert!(matches!(
+            parse(&["#<<>#"]),
+            Err(LevelError::DuplicateLadderUp)
+        ));
+        assert!(matches!(
+            parse(&["#<..#"]),
+            Err(LevelError::MissingExit)
+        ));
+        assert!(matches!(
+            parse(&["#<>*#"]),
+            Err(LevelError::DuplicateExit)
+        ));
+        assert!(matches!(
+            parse(&["#<>x#"]),
+            Err(LevelError::UnknownGlyph('x', _))
+        ));
+    }
+}
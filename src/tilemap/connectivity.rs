@@ -0,0 +1,186 @@
+//! Level connectivity submodule
+//! Flood fills over a `LevelLayout` to check that every room and walkable tile
+//! can be reached from the start, and carves corridors when they can't
+
+use std::collections::{HashMap, VecDeque};
+
+use bevy::prelude::*;
+
+use crate::{
+    misc::{dir_to_vec, Direction},
+    tilemap::{LevelLayout, Tile},
+};
+
+/// Summary of how well connected a level is
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LevelStats {
+    /// Tiles that can be walked on (or that have an enemy in them)
+    pub walkable: usize,
+    /// Walkable tiles that can be reached from the ladder up
+    pub reachable: usize,
+    pub rooms: usize,
+    /// Rooms whose center can be reached from the ladder up
+    pub reachable_rooms: usize,
+    /// Steps from the ladder up to the exit, if it can be reached
+    pub exit_distance: Option<u32>,
+}
+
+impl LevelStats {
+    pub fn is_connected(&self) -> bool {
+        self.reachable == self.walkable && self.exit_distance.is_some()
+    }
+}
+
+impl LevelLayout {
+    /// Flood fills the level from a position, returning the number of steps
+    /// needed to get to each reachable tile
+    pub fn distances(&self, from: IVec2) -> HashMap<IVec2, u32> {
+        let mut distances = HashMap::new();
+        if !self.get(from).is_some_and(is_walkable) {
+            return distances;
+        }
+
+        let mut queue = VecDeque::from([from]);
+        distances.insert(from, 0);
+        while let Some(pos) = queue.pop_front() {
+            let dist = distances[&pos];
+            for dir in Direction::iter() {
+                let next = pos + dir_to_vec(dir, 1.).as_ivec2();
+                if distances.contains_key(&next) || !self.get(next).is_some_and(is_walkable) {
+                    continue;
+                }
+                distances.insert(next, dist + 1);
+                queue.push_back(next);
+            }
+        }
+        distances
+    }
+
+    /// Checks the connectivity of the level from the ladder up
+    pub fn stats(&self) -> LevelStats {
+        let distances = self.distances(self.ladder_up);
+        LevelStats {
+            walkable: self.tiles().filter(|(_, tile)| is_walkable(tile)).count(),
+            reachable: distances.len(),
+            rooms: self.rooms.len(),
+            reachable_rooms: self
+                .rooms
+                .iter()
+                .filter(|room| distances.contains_key(&room.center()))
+                .count(),
+            exit_distance: distances.get(&self.exit).copied(),
+        }
+    }
+
+    /// Makes sure that every room can be reached from the ladder up
+    /// Unreachable rooms are joined to the closest reachable one with a new
+    /// corridor. Returns how many corridors had to be carved
+    pub fn connect_rooms(&mut self) -> usize {
+        let mut carved = 0;
+        for _ in 0..self.rooms.len() {
+            let distances = self.distances(self.ladder_up);
+            let (reached, missing): (Vec<_>, Vec<_>) = self
+                .rooms
+                .iter()
+                .map(|room| room.center())
+                .partition(|center| distances.contains_key(center));
+
+            let Some(&from) = missing.first() else { break };
+            let to = reached
+                .into_iter()
+                .min_by_key(|center| (*center - from).abs().element_sum())
+                .unwrap_or(self.ladder_up);
+
+            self.carve(from, to);
+            carved += 1;
+        }
+        carved
+    }
+
+    /// Makes sure that every walkable tile can be reached from the ladder up,
+    /// including the ones outside of rooms like cave pockets or prefab areas
+    /// Each unreachable tile is joined to the closest reachable one, in grid
+    /// order so the same layout is always carved the same way. Returns how
+    /// many corridors had to be carved
+    pub fn connect_pockets(&mut self) -> usize {
+        let mut carved = 0;
+        loop {
+            let distances = self.distances(self.ladder_up);
+            let Some(from) = self
+                .tiles()
+                .find(|(pos, tile)| is_walkable(tile) && !distances.contains_key(pos))
+                .map(|(pos, _)| pos)
+            else {
+                break;
+            };
+            let to = self
+                .tiles()
+                .map(|(pos, _)| pos)
+                .filter(|pos| distances.contains_key(pos))
+                .min_by_key(|pos| (*pos - from).abs().element_sum())
+                .unwrap_or(self.ladder_up);
+
+            self.carve(from, to);
+            carved += 1;
+        }
+        carved
+    }
+
+    /// Digs an L shaped corridor between two positions, surrounding it with
+    /// walls where there was nothing before
+    pub(crate) fn carve(&mut self, from: IVec2, to: IVec2) {
+        let corner = IVec2::new(to.x, from.y);
+        for (a, b) in [(from, corner), (corner, to)] {
+            let step = (b - a).signum();
+            let side = step.perp();
+            let mut pos = a;
+            loop {
+                if matches!(self.get(pos), None | Some(Tile::Wall)) {
+                    self.set(pos, Tile::Path);
+                }
+                for side in [pos + side, pos - side] {
+                    if self.get(side).is_none() {
+                        self.set(side, Tile::Wall);
+                    }
+                }
+                if pos == b {
+                    break;
+                }
+                pos += step;
+            }
+        }
+    }
+}
+
+/// If the player can stand in (or attack into) a tile
+pub fn is_walkable(tile: &Tile) -> bool {
+    !matches!(tile, Tile::Wall)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pockets_outside_of_rooms_are_connected() {
+        // A start area and a cave pocket with a wall between them
+        let mut tiles = HashMap::new();
+        for x in 0..7 {
+            for y in 0..3 {
+                let tile = if x == 3 { Tile::Wall } else { Tile::Ground };
+                tiles.insert(IVec2::new(x, y), tile);
+            }
+        }
+        let mut layout = LevelLayout::from_tiles(0, 0, tiles);
+        layout.set(IVec2::ZERO, Tile::LadderUp);
+        layout.exit = IVec2::new(1, 1);
+
+        let stats = layout.stats();
+        assert_eq!(stats.reachable_rooms, stats.rooms);
+        assert!(!stats.is_connected());
+
+        assert_eq!(layout.connect_pockets(), 1);
+        assert!(layout.stats().is_connected());
+        assert_eq!(layout.connect_pockets(), 0);
+    }
+}
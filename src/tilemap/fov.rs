@@ -0,0 +1,266 @@
+//! Field of view submodule
+//! Recursive shadowcasting from the player position, where walls block the
+//! sight. Tiles start unseen, become visible when they are in the field of
+//! view and stay remembered (dimmed) after that
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::{
+    data::{sight_radius, Persistent, SaveData},
+    enemy::Enemy,
+    player::Player,
+    tilemap::{Tile, Tilemap},
+};
+
+/// Transforms an octant into the first one: xx, xy, yx, yy
+const OCTANTS: [[i32; 4]; 8] = [
+    [1, 0, 0, 1],
+    [0, 1, 1, 0],
+    [0, -1, 1, 0],
+    [-1, 0, 0, 1],
+    [-1, 0, 0, -1],
+    [0, -1, -1, 0],
+    [0, 1, -1, 0],
+    [1, 0, 0, -1],
+];
+
+pub const REMEMBERED_COLOR: Color = Color::srgb(0.3, 0.3, 0.35);
+
+// ·········
+// Resources
+// ·········
+
+/// Tiles that the player can currently see
+#[derive(Resource, Default)]
+pub struct FieldOfView {
+    pub visible: HashSet<IVec2>,
+}
+
+// ··········
+// Components
+// ··········
+
+/// Color of a tile from the sprite catalogue, dimmed while it is remembered
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Tint(pub Color);
+
+#[derive(Component, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Seen {
+    #[default]
+    Unseen,
+    Remembered,
+    Visible,
+}
+
+// ·······
+// Systems
+// ·······
+
+/// Recomputes the field of view when the player moves or the level changes
+pub(crate) fn update_fov(
+    player: Query<Ref<Player>>,
+    tilemap: Res<Tilemap>,
+    mut tiles: Query<(
+        &mut Seen,
+        &mut Visibility,
+        &mut Sprite,
+        &Tint,
+    )>,
+    save_data: Res<Persistent<SaveData>>,
+    mut fov: ResMut<FieldOfView>,
+) {
+    let Ok(player) = player.get_single() else { return };
+    if !player.is_changed() && !tilemap.is_changed() {
+        return;
+    }
+
+    let blocks = |pos| tilemap.tile(pos).unwrap_or(Tile::Wall) == Tile::Wall;
+    fov.visible = field_of_view(
+        player.pos,
+        sight_radius(save_data.range_level),
+        blocks,
+    );
+
+    for (pos, cell) in tilemap.iter() {
+        let Ok((mut seen, mut visibility, mut sprite, tint)) = tiles.get_mut(cell.entity) else {
+            continue;
+        };
+        let next = if fov.visible.contains(&pos) {
+            Seen::Visible
+        } else if *seen == Seen::Unseen {
+            Seen::Unseen
+        } else {
+            Seen::Remembered
+        };
+        if *seen == next {
+            continue;
+        }
+
+        *seen = next;
+        *visibility = match next {
+            Seen::Unseen => Visibility::Hidden,
+            _ => Visibility::Inherited,
+        };
+        sprite.color = match next {
+            Seen::Remembered => remembered(tint.0),
+            _ => tint.0,
+        };
+    }
+}
+
+/// Enemies are only shown while they are in the field of view
+pub(crate) fn hide_enemies(mut enemies: Query<(&Enemy, &mut Visibility)>, fov: Res<FieldOfView>) {
+    for (enemy, mut visibility) in enemies.iter_mut() {
+        let next = if fov.visible.contains(&enemy.pos) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *visibility != next {
+            *visibility = next;
+        }
+    }
+}
+
+// ·······
+// Helpers
+// ·······
+
+/// Dims the color of a tile that is remembered but not visible
+pub fn remembered(tint: Color) -> Color {
+    let tint = tint.to_srgba();
+    let dim = REMEMBERED_COLOR.to_srgba();
+    Color::srgba(
+        tint.red * dim.red,
+        tint.green * dim.green,
+        tint.blue * dim.blue,
+        tint.alpha,
+    )
+}
+
+/// Returns every tile that can be seen from a position
+/// Tiles that block the sight are also visible, but nothing behind them is
+pub fn field_of_view(origin: IVec2, radius: u32, blocks: impl Fn(IVec2) -> bool) -> HashSet<IVec2> {
+    let mut visible = HashSet::from([origin]);
+    for transform in OCTANTS {
+        cast_light(
+            &mut visible,
+            &blocks,
+            origin,
+            radius as i32,
+            1,
+            (1., 0.),
+            transform,
+        );
+    }
+    visible
+}
+
+/// Scans one octant row by row, starting a new scan under each wall
+fn cast_light(
+    visible: &mut HashSet<IVec2>,
+    blocks: &impl Fn(IVec2) -> bool,
+    origin: IVec2,
+    radius: i32,
+    row: i32,
+    slopes: (f32, f32),
+    [xx, xy, yx, yy]: [i32; 4],
+) {
+    let (mut start, end) = slopes;
+    if start < end {
+        return;
+    }
+
+    let mut next_start = start;
+    for dist in row..=radius {
+        let mut blocked = false;
+        let dy = -dist;
+        for dx in -dist..=0 {
+            let left = (dx as f32 - 0.5) / (dy as f32 + 0.5);
+            let right = (dx as f32 + 0.5) / (dy as f32 - 0.5);
+            if start < right {
+                continue;
+            }
+            if end > left {
+                break;
+            }
+
+            let pos = origin + IVec2::new(dx * xx + dy * xy, dx * yx + dy * yy);
+            if dx * dx + dy * dy <= radius * radius {
+                visible.insert(pos);
+            }
+
+            if blocked {
+                if blocks(pos) {
+                    next_start = right;
+                } else {
+                    blocked = false;
+                    start = next_start;
+                }
+            } else if blocks(pos) && dist < radius {
+                blocked = true;
+                cast_light(
+                    visible,
+                    blocks,
+                    origin,
+                    radius,
+                    dist + 1,
+                    (start, left),
+                    [xx, xy, yx, yy],
+                );
+                next_start = right;
+            }
+        }
+        if blocked {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assets::SpriteCatalogue, testing::TestApp, GameState, PlayState};
+
+    #[test]
+    fn tiles_keep_their_tint() {
+        let mut game = TestApp::new();
+        let tint = Color::srgb(1., 0.5, 0.);
+        let mut sprites = game.app.world_mut().resource_mut::<SpriteCatalogue>();
+        for (_, entry) in sprites
+            .sprites
+            .iter_mut()
+            .filter(|(name, _)| name.starts_with("tile."))
+        {
+            entry.tint = Some(tint);
+        }
+
+        // Build the level again with the tinted catalogue
+        game.set_state(GameState::Reload);
+        game.run_until("the level to start", |world| {
+            world
+                .get_resource::<State<PlayState>>()
+                .is_some_and(|state| state.get() == &PlayState::Play)
+        });
+        game.start_level();
+
+        let world = game.app.world_mut();
+        let mut tiles = world.query::<(&Seen, &Sprite, &Tint)>();
+        let mut visible = 0;
+        for (seen, sprite, base) in tiles.iter(world) {
+            assert_eq!(base.0, tint);
+            match seen {
+                Seen::Visible => {
+                    assert_eq!(sprite.color, tint);
+                    visible += 1;
+                },
+                Seen::Remembered => assert_eq!(sprite.color, remembered(tint)),
+                Seen::Unseen => {},
+            }
+        }
+        assert!(visible > 0);
+        assert_ne!(remembered(tint), REMEMBERED_COLOR);
+    }
+}
@@ -0,0 +1,168 @@
+//! Level generation submodule
+//! Every algorithm implements `LevelGenerator`, which only builds the shape of
+//! the level. The steps shared by all of them (connecting rooms, placing the
+//! exit and creating the enemies) are done afterwards in `generate_level`
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+
+pub use self::{bsp::Bsp, caves::Caves, prefabs::Prefabs, rooms::RoomWalk};
+use crate::{
+    assets::SpriteCatalogue,
+    data::Balance,
+    enemy::{get_enemy, new_enemy, Element, EnemyType},
+    tilemap::{level_rng, EnemySpawn, LevelLayout, Tile},
+};
+
+mod bsp;
+mod caves;
+mod prefabs;
+mod rooms;
+
+// ······
+// Traits
+// ······
+
+/// An algorithm that creates the shape of a level
+pub trait LevelGenerator: Send + Sync {
+    /// Builds the tiles, rooms and ladder up of a level
+    /// Enemies are marked with `Tile::Enemy`, the exit is chosen later
+    fn generate(&self, seed: u64, level: u32, rng: &mut StdRng) -> LevelLayout;
+}
+
+// ·········
+// Resources
+// ·········
+
+/// Which generator is used at each depth
+/// Each band starts at a depth and lasts until the next one begins
+#[derive(Resource)]
+pub struct LevelGenerators {
+    pub bands: Vec<(u32, Box<dyn LevelGenerator>)>,
+}
+
+impl Default for LevelGenerators {
+    fn default() -> Self {
+        Self {
+            bands: vec![
+                (0, Box::new(RoomWalk)),
+                (3, Box::new(Bsp)),
+                (5, Box::new(Caves)),
+                (7, Box::new(Prefabs)),
+            ],
+        }
+    }
+}
+
+impl LevelGenerators {
+    /// Returns the generator for a depth
+    /// Uses the room walk if no band covers it
+    pub fn get(&self, level: u32) -> &dyn LevelGenerator {
+        self.bands
+            .iter()
+            .filter(|(from, _)| *from <= level)
+            .max_by_key(|(from, _)| *from)
+            .map_or(&RoomWalk, |(_, generator)| {
+                generator.as_ref()
+            })
+    }
+}
+
+// ·······
+// Helpers
+// ·······
+
+/// Creates a full level with a generator
+/// The same generator, seed and level always return the same layout
+pub fn generate_level(
+    generator: &dyn LevelGenerator,
+    seed: u64,
+    level: u32,
+    sprites: &SpriteCatalogue,
+    balance: &Balance,
+) -> LevelLayout {
+    let mut rng = level_rng(seed, level);
+    let mut layout = generator.generate(seed, level, &mut rng);
+
+    // Make sure everything can be reached before placing the exit
+    let carved = layout.connect_rooms() + layout.connect_pockets();
+    if carved > 0 {
+        debug!("carved {} extra corridors", carved);
+    }
+
+    // Insert ladder down or final key
+    if let Some(exit) = choose_exit(&layout, &mut rng) {
+        layout.set(
+            exit,
+            if level < 9 { Tile::LadderDown } else { Tile::Enemy },
+        );
+        layout.exit = exit;
+    }
+
+    // Create the enemy descriptions
+    let enemies = layout
+        .tiles()
+        .filter(|(_, tile)| matches!(tile, Tile::Enemy))
+        .map(|(pos, _)| pos)
+        .collect::<Vec<_>>();
+    layout.enemies = enemies
+        .into_iter()
+        .map(|pos| {
+            let (enemy, behaviour, sprite) = if level >= 9 && pos == layout.exit {
+                new_enemy(
+                    pos,
+                    EnemyType::EndGame,
+                    Element::Basic,
+                    sprites,
+                    &mut rng,
+                )
+            } else {
+                get_enemy(pos, level, sprites, balance, &mut rng)
+            };
+            EnemySpawn {
+                enemy,
+                behaviour,
+                sprite,
+            }
+        })
+        .collect();
+
+    layout
+}
+
+/// How many enemies a room has, it grows the deeper the level is
+fn enemy_count(level: u32, rng: &mut impl Rng) -> u32 {
+    let grow = (level / 3, level / 2);
+    rng.gen_range(1 + grow.0..3 + grow.1)
+}
+
+/// Picks a ground tile in the room that is farthest away from the ladder up
+/// If that room is full, it uses the farthest ground tile in the level instead
+fn choose_exit(layout: &LevelLayout, rng: &mut impl Rng) -> Option<IVec2> {
+    let distances = layout.distances(layout.ladder_up);
+    let ground = layout
+        .tiles()
+        .filter(|(pos, tile)| matches!(tile, Tile::Ground) && distances.contains_key(pos))
+        .map(|(pos, _)| pos)
+        .collect::<Vec<_>>();
+
+    let farthest_room = layout
+        .rooms
+        .iter()
+        .filter_map(|room| Some((room, *distances.get(&room.center())?)))
+        .max_by_key(|(_, dist)| *dist)
+        .map(|(room, _)| room);
+
+    if let Some(room) = farthest_room {
+        let in_room = ground
+            .iter()
+            .filter(|pos| room.contains(**pos))
+            .copied()
+            .collect::<Vec<_>>();
+        if let Some(exit) = in_room.choose(rng) {
+            return Some(*exit);
+        }
+    }
+
+    ground.into_iter().max_by_key(|pos| distances[pos])
+}
@@ -0,0 +1,162 @@
+//! Binary space partitioning generator
+//! The level is split in two again and again until the pieces are small, then
+//! every piece gets a room that is joined to the room of its sibling
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+
+use super::{enemy_count, LevelGenerator};
+use crate::tilemap::{LevelLayout, Room, Tile};
+
+/// Smallest area that can be split off, walls included
+const MIN_LEAF: IVec2 = IVec2::new(10, 8);
+/// Smallest room, walls included
+const MIN_ROOM: IVec2 = IVec2::new(6, 5);
+
+pub struct Bsp;
+
+impl LevelGenerator for Bsp {
+    fn generate(&self, seed: u64, level: u32, rng: &mut StdRng) -> LevelLayout {
+        let size = IVec2::new(
+            36 + level as i32 * 3,
+            26 + level as i32 * 2,
+        );
+
+        let mut rooms = Vec::new();
+        let mut links = Vec::new();
+        split(
+            IVec2::ZERO,
+            size,
+            rng,
+            &mut rooms,
+            &mut links,
+        );
+
+        let mut tiles = HashMap::new();
+        for room in &rooms {
+            for (x, y) in (room.min.x..=room.max.x).cartesian_product(room.min.y..=room.max.y) {
+                let edge = x == room.min.x || x == room.max.x || y == room.min.y || y == room.max.y;
+                tiles.insert(
+                    IVec2::new(x, y),
+                    if edge { Tile::Wall } else { Tile::Ground },
+                );
+            }
+        }
+
+        let mut layout = LevelLayout::from_tiles(seed, level, tiles);
+        for (a, b) in links {
+            layout.carve(a, b);
+        }
+
+        // Enemies
+        for room in &rooms {
+            let mut ground = (room.min.x + 1..room.max.x)
+                .cartesian_product(room.min.y + 1..room.max.y)
+                .map(|(x, y)| IVec2::new(x, y))
+                .filter(|pos| matches!(layout.get(*pos), Some(Tile::Ground)))
+                .collect::<Vec<_>>();
+            ground.shuffle(rng);
+            for pos in ground.into_iter().take(enemy_count(level, rng) as usize) {
+                layout.set(pos, Tile::Enemy);
+            }
+        }
+
+        // Insert ladder up
+        let ladder_up = rooms[0].center();
+        layout.set(ladder_up, Tile::LadderUp);
+        layout.ladder_up = ladder_up;
+        layout.rooms = rooms;
+        layout
+    }
+}
+
+/// Splits an area in two until it is too small, placing a room in every leaf
+/// Returns the center of one of the rooms inside, to link it with its sibling
+fn split(
+    min: IVec2,
+    size: IVec2,
+    rng: &mut impl Rng,
+    rooms: &mut Vec<Room>,
+    links: &mut Vec<(IVec2, IVec2)>,
+) -> IVec2 {
+    let can_split = size.cmpge(MIN_LEAF * 2);
+    let vertical = match (can_split.x, can_split.y) {
+        (false, false) => {
+            let room_size = IVec2::new(
+                rng.gen_range(MIN_ROOM.x..=size.x),
+                rng.gen_range(MIN_ROOM.y..=size.y),
+            );
+            let offset = IVec2::new(
+                rng.gen_range(0..=size.x - room_size.x),
+                rng.gen_range(0..=size.y - room_size.y),
+            );
+            let room = Room {
+                min: min + offset,
+                max: min + offset + room_size - IVec2::ONE,
+            };
+            rooms.push(room);
+            return room.center();
+        },
+        (true, false) => true,
+        (false, true) => false,
+        // Prefer cutting the longest side
+        (true, true) => {
+            let ratio = size.x as f32 / size.y as f32;
+            if ratio > 1.25 {
+                true
+            } else if ratio < 0.8 {
+                false
+            } else {
+                rng.gen_bool(0.5)
+            }
+        },
+    };
+
+    let (a, b) = if vertical {
+        let cut = rng.gen_range(MIN_LEAF.x..=size.x - MIN_LEAF.x);
+        (
+            split(
+                min,
+                IVec2::new(cut, size.y),
+                rng,
+                rooms,
+                links,
+            ),
+            split(
+                min + IVec2::new(cut, 0),
+                IVec2::new(size.x - cut, size.y),
+                rng,
+                rooms,
+                links,
+            ),
+        )
+    } else {
+        let cut = rng.gen_range(MIN_LEAF.y..=size.y - MIN_LEAF.y);
+        (
+            split(
+                min,
+                IVec2::new(size.x, cut),
+                rng,
+                rooms,
+                links,
+            ),
+            split(
+                min + IVec2::new(0, cut),
+                IVec2::new(size.x, size.y - cut),
+                rng,
+                rooms,
+                links,
+            ),
+        )
+    };
+
+    links.push((a, b));
+    if rng.gen_bool(0.5) {
+        a
+    } else {
+        b
+    }
+}
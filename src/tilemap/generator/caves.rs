@@ -0,0 +1,127 @@
+//! Cellular automata generator
+//! Starts from random noise and smooths it until it looks like a cave, then
+//! keeps only the biggest open area so that everything can be reached
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use bevy::prelude::*;
+use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+
+use super::{enemy_count, LevelGenerator};
+use crate::{
+    misc::{dir_to_vec, Direction},
+    tilemap::{LevelLayout, Tile},
+};
+
+/// Chance of a tile starting as a wall
+const FILL: f64 = 0.45;
+/// Times the smoothing rule is applied
+const STEPS: usize = 5;
+/// Open tiles per group of enemies, like a room would have
+const TILES_PER_ROOM: usize = 60;
+
+pub struct Caves;
+
+impl LevelGenerator for Caves {
+    fn generate(&self, seed: u64, level: u32, rng: &mut StdRng) -> LevelLayout {
+        let size = IVec2::new(
+            32 + level as i32 * 2,
+            22 + level as i32 * 2,
+        );
+        let cells = (0..size.x)
+            .cartesian_product(0..size.y)
+            .map(|(x, y)| IVec2::new(x, y));
+        let border =
+            |pos: IVec2| pos.x == 0 || pos.y == 0 || pos.x == size.x - 1 || pos.y == size.y - 1;
+
+        // Random noise
+        let mut walls = cells
+            .clone()
+            .map(|pos| (pos, border(pos) || rng.gen_bool(FILL)))
+            .collect::<HashMap<_, _>>();
+
+        // A tile becomes a wall if most of its neighbours are walls
+        for _ in 0..STEPS {
+            walls = cells
+                .clone()
+                .map(|pos| {
+                    let around = (-1..=1)
+                        .cartesian_product(-1..=1)
+                        .filter(|&(x, y)| *walls.get(&(pos + IVec2::new(x, y))).unwrap_or(&true))
+                        .count();
+                    (pos, border(pos) || around >= 5)
+                })
+                .collect();
+        }
+
+        // Keep the biggest open area
+        let mut regions: Vec<Vec<IVec2>> = Vec::new();
+        let mut visited = HashSet::new();
+        for pos in cells.clone() {
+            if walls[&pos] || visited.contains(&pos) {
+                continue;
+            }
+            let mut region = vec![pos];
+            let mut queue = VecDeque::from([pos]);
+            visited.insert(pos);
+            while let Some(pos) = queue.pop_front() {
+                for dir in Direction::iter() {
+                    let next = pos + dir_to_vec(dir, 1.).as_ivec2();
+                    if walls[&next] || visited.contains(&next) {
+                        continue;
+                    }
+                    visited.insert(next);
+                    region.push(next);
+                    queue.push_back(next);
+                }
+            }
+            regions.push(region);
+        }
+        let mut open = regions
+            .into_iter()
+            .max_by_key(|region| region.len())
+            .unwrap_or_default();
+        open.sort_by_key(|pos| (pos.y, pos.x));
+
+        // Only walls that touch the cave are kept
+        let mut tiles = HashMap::new();
+        for &pos in &open {
+            tiles.insert(pos, Tile::Ground);
+            for (x, y) in (-1..=1).cartesian_product(-1..=1) {
+                tiles.entry(pos + IVec2::new(x, y)).or_insert(Tile::Wall);
+            }
+        }
+
+        // A small fallback room in case the noise left nothing open
+        if open.is_empty() {
+            for (x, y) in (0..5).cartesian_product(0..5) {
+                let edge = x == 0 || y == 0 || x == 4 || y == 4;
+                tiles.insert(
+                    IVec2::new(x, y),
+                    if edge { Tile::Wall } else { Tile::Ground },
+                );
+            }
+            open = (1..4)
+                .cartesian_product(1..4)
+                .map(|(x, y)| IVec2::new(x, y))
+                .collect();
+        }
+
+        // Enemies and ladder up
+        open.shuffle(rng);
+        let ladder_up = open[0];
+        tiles.insert(ladder_up, Tile::LadderUp);
+
+        let num_enemies = (0..(open.len() / TILES_PER_ROOM).max(1))
+            .map(|_| enemy_count(level, rng) as usize)
+            .sum::<usize>();
+        for &pos in open.iter().skip(1).take(num_enemies) {
+            tiles.insert(pos, Tile::Enemy);
+        }
+
+        let mut layout = LevelLayout::from_tiles(seed, level, tiles);
+        layout.ladder_up = ladder_up;
+        layout
+    }
+}
@@ -0,0 +1,153 @@
+//! Prefab stitcher generator
+//! Hand drawn chunks are placed next to each other following a random walk
+//! Neighbouring chunks share a wall, which gets a door in the middle
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+
+use super::{enemy_count, LevelGenerator};
+use crate::{
+    misc::{dir_to_vec, Direction},
+    tilemap::{LevelLayout, Room, Tile},
+};
+
+/// Size of a chunk, walls included
+const CHUNK: IVec2 = IVec2::new(11, 9);
+
+/// Inside of the chunks, without the surrounding walls
+/// The middle of every side and the center must be open so that doors and
+/// corridors always lead somewhere
+const PREFABS: [[&str; 7]; 5] = [
+    [
+        ".........",
+        ".........",
+        ".........",
+        ".........",
+        ".........",
+        ".........",
+        ".........",
+    ],
+    [
+        ".........",
+        ".#..#..#.",
+        ".........",
+        ".........",
+        ".........",
+        ".#..#..#.",
+        ".........",
+    ],
+    [
+        ".........",
+        ".##...##.",
+        ".#.....#.",
+        ".........",
+        ".#.....#.",
+        ".##...##.",
+        ".........",
+    ],
+    [
+        ".........",
+        ".###.###.",
+        ".#.....#.",
+        ".........",
+        ".#.....#.",
+        ".###.###.",
+        ".........",
+    ],
+    [
+        ".........",
+        "..#......",
+        ".....#...",
+        ".#.......",
+        "......#..",
+        "...#.....",
+        ".........",
+    ],
+];
+
+pub struct Prefabs;
+
+impl LevelGenerator for Prefabs {
+    fn generate(&self, seed: u64, level: u32, rng: &mut StdRng) -> LevelLayout {
+        let chunks = rng.gen_range(4 + level / 3..=6 + level / 2);
+
+        // Walk around a grid to choose where the chunks go
+        let mut chunk_indices: Vec<IVec2> = Vec::new();
+        let mut chunk_pos = IVec2::ZERO;
+        for _ in 0..chunks {
+            while chunk_indices.contains(&chunk_pos) {
+                let dir: Direction = rng.gen();
+                chunk_pos += dir_to_vec(&dir, 1.).as_ivec2();
+            }
+            chunk_indices.push(chunk_pos);
+        }
+
+        // Stitch the chunks, sharing the walls between them
+        let stride = CHUNK - IVec2::ONE;
+        let mut tiles = HashMap::new();
+        let mut rooms = Vec::new();
+        for &index in &chunk_indices {
+            let min = index * stride;
+            let prefab = PREFABS.choose(rng).expect("There are prefabs");
+            let flip = (rng.gen_bool(0.5), rng.gen_bool(0.5));
+
+            for (x, y) in (0..CHUNK.x).cartesian_product(0..CHUNK.y) {
+                let edge = x == 0 || y == 0 || x == CHUNK.x - 1 || y == CHUNK.y - 1;
+                let tile = if edge {
+                    Tile::Wall
+                } else {
+                    let col = if flip.0 { CHUNK.x - 2 - x } else { x - 1 };
+                    let row = if flip.1 { y - 1 } else { CHUNK.y - 2 - y };
+                    match prefab[row as usize].as_bytes()[col as usize] {
+                        b'#' => Tile::Wall,
+                        _ => Tile::Ground,
+                    }
+                };
+                // Shared walls are only written once
+                tiles.entry(min + IVec2::new(x, y)).or_insert(tile);
+            }
+
+            rooms.push(Room {
+                min,
+                max: min + CHUNK - IVec2::ONE,
+            });
+        }
+
+        // Open doors between neighbours
+        for (i, &a) in chunk_indices.iter().enumerate() {
+            for dir in Direction::iter() {
+                let offset = dir_to_vec(dir, 1.).as_ivec2();
+                if chunk_indices[i + 1..].contains(&(a + offset)) {
+                    let center = a * stride + CHUNK / 2;
+                    let door = center + offset * (CHUNK / 2);
+                    tiles.insert(door, Tile::Path);
+                }
+            }
+        }
+
+        // Enemies
+        for room in &rooms {
+            let mut ground = (room.min.x + 1..room.max.x)
+                .cartesian_product(room.min.y + 1..room.max.y)
+                .map(|(x, y)| IVec2::new(x, y))
+                .filter(|pos| matches!(tiles.get(pos), Some(Tile::Ground)))
+                .collect::<Vec<_>>();
+            ground.shuffle(rng);
+            for pos in ground.into_iter().take(enemy_count(level, rng) as usize) {
+                tiles.insert(pos, Tile::Enemy);
+            }
+        }
+
+        // Insert ladder up
+        let ladder_up = rooms[0].center();
+        tiles.insert(ladder_up, Tile::LadderUp);
+
+        let mut layout = LevelLayout::from_tiles(seed, level, tiles);
+        layout.rooms = rooms;
+        layout.ladder_up = ladder_up;
+        layout
+    }
+}
@@ -0,0 +1,143 @@
+//! Room walk generator
+//! The original algorithm of the game. Rooms are placed in a grid by walking
+//! in random directions, and neighbouring rooms are joined by straight
+//! corridors
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+
+use super::{enemy_count, LevelGenerator};
+use crate::{
+    misc::{dir_to_vec, Direction},
+    tilemap::{LevelLayout, Room, Tile, ROOM_SEP},
+};
+
+pub struct RoomWalk;
+
+impl LevelGenerator for RoomWalk {
+    fn generate(&self, seed: u64, level: u32, rng: &mut StdRng) -> LevelLayout {
+        let rooms = (
+            2 + (level * 0.3 as u32).clamp(0, 3),
+            5 + (level * 0.5 as u32).clamp(0, 5),
+        );
+        let size_x = (ROOM_SEP.x / 2 + 1, ROOM_SEP.x - 4);
+        let size_y = (ROOM_SEP.y / 2 + 1, ROOM_SEP.y - 4);
+
+        let rooms = rng.gen_range(rooms.0..=rooms.1);
+        // Kept in insertion order so that corridors are always dug the same way
+        let mut room_indices: Vec<IVec2> = Vec::new();
+        let mut room_rects = Vec::new();
+        let mut room_pos = IVec2::ZERO;
+
+        let mut tiles = HashMap::new();
+
+        // Generate rooms
+        for _ in 0..rooms {
+            loop {
+                if !room_indices.contains(&room_pos) {
+                    room_indices.push(room_pos);
+                    break;
+                };
+                let dir: Direction = rng.gen();
+                let global_offset = dir_to_vec(&dir, 1.).as_ivec2();
+                room_pos += global_offset;
+            }
+
+            let size = UVec2::new(
+                rng.gen_range(size_x.0..=size_x.1),
+                rng.gen_range(size_y.0..=size_y.1),
+            );
+
+            let offset = IVec2::new(
+                rng.gen_range(0..(ROOM_SEP.x - size.x)) as i32 + room_pos.x * ROOM_SEP.x as i32,
+                rng.gen_range(0..(ROOM_SEP.y - size.y)) as i32 + room_pos.y * ROOM_SEP.y as i32,
+            );
+
+            generate_room(&mut tiles, rng, size, offset, level);
+            room_rects.push(Room {
+                min: offset,
+                max: offset + size.as_ivec2() + IVec2::ONE,
+            });
+        }
+
+        // Generate corridors
+        for (i, &a) in room_indices.iter().enumerate() {
+            for dir in Direction::iter() {
+                let offset = dir_to_vec(dir, 1.).as_ivec2();
+                let b = a + offset;
+                let sep = match dir {
+                    Direction::North | Direction::South => ROOM_SEP.x * 2,
+                    Direction::East | Direction::West => ROOM_SEP.y * 2,
+                };
+                if room_indices[i + 1..].contains(&b) {
+                    // Corridor
+                    let center_a = a * ROOM_SEP.as_ivec2() + ROOM_SEP.as_ivec2() / 2;
+                    let mut first_wall = false;
+                    for pos in 0..sep {
+                        let pos = center_a + pos as i32 * offset;
+                        // Find the first wall and start laying paths
+                        if !first_wall {
+                            if let Some(Tile::Wall) = tiles.get(&pos) {
+                                first_wall = true;
+                                tiles.insert(pos, Tile::Path);
+                                continue;
+                            }
+                        }
+                        // Lay paths until next wall
+                        else if let Some(Tile::Wall) = tiles.insert(pos, Tile::Path) {
+                            break;
+                        }
+                        let offset = offset.perp();
+                        tiles.entry(pos + offset).or_insert(Tile::Wall);
+                        tiles.entry(pos - offset).or_insert(Tile::Wall);
+                    }
+                }
+            }
+        }
+
+        // Insert ladder up
+        let ladder_up = ROOM_SEP.as_ivec2() / 2;
+        tiles.insert(ladder_up, Tile::LadderUp);
+
+        let mut layout = LevelLayout::from_tiles(seed, level, tiles);
+        layout.rooms = room_rects;
+        layout.ladder_up = ladder_up;
+        layout
+    }
+}
+
+fn generate_room(
+    tiles: &mut HashMap<IVec2, Tile>,
+    rng: &mut impl Rng,
+    size: UVec2,
+    offset: IVec2,
+    level: u32,
+) {
+    let num_enemies = enemy_count(level, rng);
+
+    let mut indices: Vec<(u32, u32)> = (1..=size.x).cartesian_product(0..=size.y).collect();
+    indices.shuffle(rng);
+    let enemy_tiles = indices.get(0..num_enemies as usize);
+
+    for (x, y) in (0..=size.x + 1).cartesian_product(0..=size.y + 1) {
+        let tile = if x == 0 || x == size.x + 1 || y == 0 || y == size.y + 1 {
+            Tile::Wall
+        } else if let Some(tiles) = enemy_tiles {
+            if tiles.contains(&(x, y)) {
+                Tile::Enemy
+            } else {
+                Tile::Ground
+            }
+        } else {
+            Tile::Ground
+        };
+
+        tiles.insert(
+            IVec2::new(x as i32, y as i32) + offset,
+            tile,
+        );
+    }
+}
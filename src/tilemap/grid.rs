@@ -0,0 +1,143 @@
+//! Tile grid submodule
+//! The spawned level is stored in a dense grid split in square chunks, so
+//! looking up a tile or its neighbours is only an index calculation. Chunks
+//! that have no tiles are never allocated
+
+use bevy::prelude::*;
+
+use crate::{
+    misc::{dir_to_vec, Direction},
+    tilemap::Tile,
+};
+
+/// Width and height of a chunk
+const CHUNK_SIZE: i32 = 16;
+const CHUNK_AREA: usize = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+// ·········
+// Resources
+// ·········
+
+/// Everything that is known about a spawned tile
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TileCell {
+    pub tile: Tile,
+    /// Entity with the sprite of the tile
+    pub entity: Entity,
+    /// Enemy or player standing on the tile
+    pub occupant: Option<Entity>,
+}
+
+/// Grid with the tiles of the current level
+#[derive(Resource, Default)]
+pub struct Tilemap {
+    /// Position of the bottom left tile
+    origin: IVec2,
+    size: UVec2,
+    /// Number of chunks in each row
+    chunks_x: usize,
+    chunks: Vec<Option<Box<[Option<TileCell>]>>>,
+}
+
+impl Tilemap {
+    /// Creates an empty grid covering a rectangle
+    pub fn new(origin: IVec2, size: UVec2) -> Self {
+        let chunks = (size.as_ivec2() + CHUNK_SIZE - 1) / CHUNK_SIZE;
+        Self {
+            origin,
+            size,
+            chunks_x: chunks.x as usize,
+            chunks: vec![None; (chunks.x * chunks.y) as usize],
+        }
+    }
+
+    /// Smallest and biggest positions inside the grid
+    pub fn bounds(&self) -> (IVec2, IVec2) {
+        (
+            self.origin,
+            self.origin + self.size.as_ivec2() - IVec2::ONE,
+        )
+    }
+
+    pub fn contains(&self, pos: IVec2) -> bool {
+        let local = pos - self.origin;
+        local.cmpge(IVec2::ZERO).all() && local.cmplt(self.size.as_ivec2()).all()
+    }
+
+    pub fn get(&self, pos: IVec2) -> Option<&TileCell> {
+        let (chunk, cell) = self.index(pos)?;
+        self.chunks[chunk].as_ref()?[cell].as_ref()
+    }
+
+    pub fn get_mut(&mut self, pos: IVec2) -> Option<&mut TileCell> {
+        let (chunk, cell) = self.index(pos)?;
+        self.chunks[chunk].as_mut()?[cell].as_mut()
+    }
+
+    /// Adds a tile, it is ignored if it is out of bounds
+    pub fn insert(&mut self, pos: IVec2, cell: TileCell) {
+        let Some((chunk, index)) = self.index(pos) else { return };
+        let chunk = self.chunks[chunk].get_or_insert_with(|| vec![None; CHUNK_AREA].into());
+        chunk[index] = Some(cell);
+    }
+
+    /// Entity of the tile in a position
+    pub fn get_tile(&self, pos: IVec2) -> Option<Entity> {
+        self.get(pos).map(|cell| cell.entity)
+    }
+
+    pub fn tile(&self, pos: IVec2) -> Option<Tile> {
+        self.get(pos).map(|cell| cell.tile)
+    }
+
+    pub fn occupant(&self, pos: IVec2) -> Option<Entity> {
+        self.get(pos)?.occupant
+    }
+
+    pub fn set_occupant(&mut self, pos: IVec2, occupant: Option<Entity>) {
+        if let Some(cell) = self.get_mut(pos) {
+            cell.occupant = occupant;
+        }
+    }
+
+    /// Moves whatever is in a tile to another one
+    pub fn move_occupant(&mut self, from: IVec2, to: IVec2) {
+        let occupant = self.get_mut(from).and_then(|cell| cell.occupant.take());
+        self.set_occupant(to, occupant);
+    }
+
+    /// Tiles right next to a position, in the order of `Direction::iter`
+    pub fn neighbours(&self, pos: IVec2) -> impl Iterator<Item = (IVec2, &TileCell)> {
+        Direction::iter().filter_map(move |dir| {
+            let next = pos + dir_to_vec(dir, 1.).as_ivec2();
+            Some((next, self.get(next)?))
+        })
+    }
+
+    /// Tiles in a row, from left to right
+    pub fn row(&self, y: i32) -> impl Iterator<Item = (IVec2, &TileCell)> {
+        (self.origin.x..self.origin.x + self.size.x as i32).filter_map(move |x| {
+            let pos = IVec2::new(x, y);
+            Some((pos, self.get(pos)?))
+        })
+    }
+
+    /// Every tile, row by row starting from the bottom
+    pub fn iter(&self) -> impl Iterator<Item = (IVec2, &TileCell)> {
+        (self.origin.y..self.origin.y + self.size.y as i32).flat_map(move |y| self.row(y))
+    }
+
+    /// Chunk and cell index of a position
+    fn index(&self, pos: IVec2) -> Option<(usize, usize)> {
+        if !self.contains(pos) {
+            return None;
+        }
+        let local = pos - self.origin;
+        let chunk = local / CHUNK_SIZE;
+        let cell = local % CHUNK_SIZE;
+        Some((
+            chunk.y as usize * self.chunks_x + chunk.x as usize,
+            (cell.y * CHUNK_SIZE + cell.x) as usize,
+        ))
+    }
+}
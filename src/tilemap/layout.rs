@@ -0,0 +1,199 @@
+//! Level layout submodule
+//! A pure description of a level, independent of the ECS
+//! The generator produces it and `spawn_level` turns it into entities, so it
+//! can be inspected without a running app or loaded assets
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    enemy::{Behaviour, Enemy},
+    tilemap::Tile,
+};
+
+// ·········
+// Resources
+// ·········
+
+/// Everything needed to build a level
+/// The tiles are stored in a dense grid that covers the bounds of the level,
+/// positions outside of any room or corridor are empty
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct LevelLayout {
+    /// Seed of the run that generated this level
+    pub seed: u64,
+    /// Depth of the level, starting at 0
+    pub level: u32,
+    /// Position of the bottom left corner of the grid
+    pub origin: IVec2,
+    /// Number of columns and rows in the grid
+    pub size: UVec2,
+    tiles: Vec<Option<Tile>>,
+    /// Rooms in the order they were generated, the first one is the start
+    pub rooms: Vec<Room>,
+    /// Enemies (and pickups) to spawn, one per `Tile::Enemy`
+    pub enemies: Vec<EnemySpawn>,
+    /// Where the player starts and where they can go back to the shop
+    pub ladder_up: IVec2,
+    /// The ladder down, or the final jewel on the last level
+    pub exit: IVec2,
+}
+
+impl LevelLayout {
+    /// Creates a layout with a grid that fits all the tiles
+    /// Rooms, enemies and ladders are left for the generator to fill
+    pub fn from_tiles(seed: u64, level: u32, tiles: HashMap<IVec2, Tile>) -> Self {
+        let min = tiles.keys().copied().reduce(IVec2::min).unwrap_or_default();
+        let max = tiles.keys().copied().reduce(IVec2::max).unwrap_or_default();
+        let size = (max - min + IVec2::ONE).max(IVec2::ZERO).as_uvec2();
+
+        let mut layout = Self {
+            seed,
+            level,
+            origin: min,
+            size,
+            tiles: vec![None; (size.x * size.y) as usize],
+            rooms: Vec::new(),
+            enemies: Vec::new(),
+            ladder_up: IVec2::ZERO,
+            exit: IVec2::ZERO,
+        };
+        for (pos, tile) in tiles {
+            layout.set(pos, tile);
+        }
+        layout
+    }
+
+    /// Returns the tile at a position, if there is any
+    pub fn get(&self, pos: IVec2) -> Option<&Tile> {
+        self.index(pos).and_then(|i| self.tiles[i].as_ref())
+    }
+
+    /// Replaces the tile at a position, returning the previous one
+    /// Positions outside of the grid are ignored
+    pub fn set(&mut self, pos: IVec2, tile: Tile) -> Option<Tile> {
+        let i = self.index(pos)?;
+        self.tiles[i].replace(tile)
+    }
+
+    /// Iterates over all the tiles, row by row from the bottom left corner
+    pub fn tiles(&self) -> impl Iterator<Item = (IVec2, &Tile)> {
+        self.tiles.iter().enumerate().filter_map(|(i, tile)| {
+            let tile = tile.as_ref()?;
+            let i = i as u32;
+            Some((
+                self.origin + UVec2::new(i % self.size.x, i / self.size.x).as_ivec2(),
+                tile,
+            ))
+        })
+    }
+
+    /// Returns the room that contains a position, walls included
+    pub fn room_at(&self, pos: IVec2) -> Option<&Room> {
+        self.rooms.iter().find(|room| room.contains(pos))
+    }
+
+    fn index(&self, pos: IVec2) -> Option<usize> {
+        let p = pos - self.origin;
+        if p.x < 0 || p.y < 0 || p.x >= self.size.x as i32 || p.y >= self.size.y as i32 {
+            return None;
+        }
+        Some((p.y as u32 * self.size.x + p.x as u32) as usize)
+    }
+}
+
+/// A rectangular room, including its walls
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Room {
+    pub min: IVec2,
+    pub max: IVec2,
+}
+
+impl Room {
+    pub fn center(&self) -> IVec2 {
+        (self.min + self.max) / 2
+    }
+
+    pub fn contains(&self, pos: IVec2) -> bool {
+        pos.cmpge(self.min).all() && pos.cmple(self.max).all()
+    }
+}
+
+/// Description of an enemy that will be spawned with the level
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EnemySpawn {
+    pub enemy: Enemy,
+    pub behaviour: Behaviour,
+    /// Index of the sprite in the atlas
+    pub sprite: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        assets::SpriteCatalogue,
+        data::Balance,
+        tilemap::{generator, LevelGenerators, Tile},
+    };
+
+    #[test]
+    fn generated_levels_can_be_finished() {
+        let generators = LevelGenerators::default();
+        let sprites = SpriteCatalogue::default();
+        let balance = Balance::default();
+
+        // Every depth, which covers all the generator bands
+        for level in 0..10 {
+            for seed in [0, 1, 42, u64::MAX] {
+                let layout = generator::generate_level(
+                    generators.get(level),
+                    seed,
+                    level,
+                    &sprites,
+                    &balance,
+                );
+                let stats = layout.stats();
+                let case = format!("level {} seed {}", level, seed);
+
+                assert_eq!(
+                    layout.get(layout.ladder_up),
+                    Some(&Tile::LadderUp),
+                    "{}",
+                    case
+                );
+                assert_eq!(
+                    stats.reachable_rooms, stats.rooms,
+                    "{}",
+                    case
+                );
+                // Every walkable tile, not only the rooms, can be reached
+                assert_eq!(
+                    stats.reachable, stats.walkable,
+                    "{}",
+                    case
+                );
+                assert!(
+                    stats.exit_distance.is_some(),
+                    "{}",
+                    case
+                );
+                let exit = if level < 9 { Tile::LadderDown } else { Tile::Enemy };
+                assert_eq!(
+                    layout.get(layout.exit),
+                    Some(&exit),
+                    "{}",
+                    case
+                );
+                assert!(
+                    layout
+                        .tiles()
+                        .all(|(pos, tile)| *tile != Tile::LadderDown || pos == layout.exit),
+                    "{}",
+                    case
+                );
+            }
+        }
+    }
+}
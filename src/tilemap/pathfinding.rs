@@ -0,0 +1,199 @@
+//! Pathfinding submodule
+//! A* and Dijkstra searches over the spawned tilemap. Ground, paths and
+//! ladders can be walked normally, while occupied tiles are allowed but more
+//! expensive, since whoever is there will probably move away
+
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    misc::{dir_to_vec, Direction},
+    player::Player,
+    tilemap::{Tile, Tilemap},
+};
+
+/// Cost of going through a tile with someone in it
+pub const OCCUPIED_COST: u32 = 4;
+
+// ·········
+// Resources
+// ·········
+
+/// Steps needed to reach the player from every tile
+/// It is recomputed each time the player ends their turn, so every enemy can
+/// share it instead of searching on its own
+#[derive(Resource, Default)]
+pub struct PlayerDistances {
+    pub target: IVec2,
+    distances: HashMap<IVec2, u32>,
+}
+
+impl PlayerDistances {
+    /// Computes the distances to the player from every tile it can reach
+    pub fn new(target: IVec2, cost: impl Fn(IVec2) -> Option<u32>) -> Self {
+        Self {
+            target,
+            distances: dijkstra(target, cost),
+        }
+    }
+
+    pub fn get(&self, pos: IVec2) -> Option<u32> {
+        self.distances.get(&pos).copied()
+    }
+
+    /// Neighbour that gets closer to the player
+    pub fn step_toward(&self, from: IVec2) -> Option<IVec2> {
+        let current = self.get(from)?;
+        neighbours(from)
+            .filter_map(|pos| Some((pos, self.get(pos)?)))
+            .filter(|(_, dist)| *dist < current)
+            .min_by_key(|(_, dist)| *dist)
+            .map(|(pos, _)| pos)
+    }
+
+    /// Neighbour that gets farther from the player
+    pub fn step_away(&self, from: IVec2) -> Option<IVec2> {
+        let current = self.get(from)?;
+        neighbours(from)
+            .filter_map(|pos| Some((pos, self.get(pos)?)))
+            .filter(|(_, dist)| *dist > current)
+            .max_by_key(|(_, dist)| *dist)
+            .map(|(pos, _)| pos)
+    }
+}
+
+// ·······
+// Systems
+// ·······
+
+pub(crate) fn update_distances(
+    player: Query<&Player>,
+    tilemap: Res<Tilemap>,
+    mut distances: ResMut<PlayerDistances>,
+) {
+    let Ok(player) = player.get_single() else { return };
+    *distances = PlayerDistances::new(player.pos, |pos| {
+        tilemap.enemy_cost(pos)
+    });
+}
+
+// ·······
+// Helpers
+// ·······
+
+impl Tilemap {
+    /// How expensive it is to step into a tile, `None` if it can't be walked on
+    pub fn cost(&self, pos: IVec2) -> Option<u32> {
+        let cell = self.get(pos)?;
+        match (cell.tile, cell.occupant) {
+            (Tile::Wall | Tile::Final, _) => None,
+            (_, Some(_)) => Some(OCCUPIED_COST),
+            _ => Some(1),
+        }
+    }
+
+    /// How expensive it is for an enemy to step into a tile
+    /// Enemies walk on ground and paths, but they never stand on ladders
+    pub fn enemy_cost(&self, pos: IVec2) -> Option<u32> {
+        let cell = self.get(pos)?;
+        match (cell.tile, cell.occupant) {
+            (Tile::Ground | Tile::Path, Some(_)) => Some(OCCUPIED_COST),
+            (Tile::Ground | Tile::Path, None) => Some(1),
+            _ => None,
+        }
+    }
+
+    /// Shortest path between two tiles, see `find_path`
+    pub fn path(&self, from: IVec2, to: IVec2) -> Option<Vec<IVec2>> {
+        find_path(from, to, |pos| self.cost(pos))
+    }
+
+    /// First tile to move to in order to get from one tile to another
+    pub fn next_step(&self, from: IVec2, to: IVec2) -> Option<IVec2> {
+        self.path(from, to)?.first().copied()
+    }
+}
+
+pub fn neighbours(pos: IVec2) -> impl Iterator<Item = IVec2> {
+    Direction::iter().map(move |dir| pos + dir_to_vec(dir, 1.).as_ivec2())
+}
+
+/// A* search between two tiles
+/// Returns the tiles to walk through, without the starting one and ending on
+/// the target. The target is always considered reachable if it is next to a
+/// walkable tile, so it can be used to path towards another character
+pub fn find_path(
+    from: IVec2,
+    to: IVec2,
+    cost: impl Fn(IVec2) -> Option<u32>,
+) -> Option<Vec<IVec2>> {
+    let heuristic = |pos: IVec2| (to - pos).abs().element_sum() as u32;
+
+    let mut queue = BinaryHeap::from([Reverse((heuristic(from), 0, from.x, from.y))]);
+    let mut costs = HashMap::from([(from, 0)]);
+    let mut came_from = HashMap::new();
+
+    while let Some(Reverse((_, current, x, y))) = queue.pop() {
+        let pos = IVec2::new(x, y);
+        if pos == to {
+            let mut path = vec![pos];
+            let mut step = pos;
+            while let Some(&prev) = came_from.get(&step) {
+                path.push(prev);
+                step = prev;
+            }
+            path.pop();
+            path.reverse();
+            return Some(path);
+        }
+        if current > costs[&pos] {
+            continue;
+        }
+
+        for next in neighbours(pos) {
+            let step = if next == to { Some(1) } else { cost(next) };
+            let Some(step) = step else { continue };
+            let next_cost = current + step;
+            if costs.get(&next).is_some_and(|c| *c <= next_cost) {
+                continue;
+            }
+            costs.insert(next, next_cost);
+            came_from.insert(next, pos);
+            queue.push(Reverse((
+                next_cost + heuristic(next),
+                next_cost,
+                next.x,
+                next.y,
+            )));
+        }
+    }
+    None
+}
+
+/// Dijkstra search from a tile to every reachable tile
+/// Returns the cost of the cheapest path from the origin to each one
+pub fn dijkstra(from: IVec2, cost: impl Fn(IVec2) -> Option<u32>) -> HashMap<IVec2, u32> {
+    let mut queue = BinaryHeap::from([Reverse((0, from.x, from.y))]);
+    let mut costs = HashMap::from([(from, 0)]);
+
+    while let Some(Reverse((current, x, y))) = queue.pop() {
+        let pos = IVec2::new(x, y);
+        if current > costs[&pos] {
+            continue;
+        }
+        for next in neighbours(pos) {
+            let Some(step) = cost(next) else { continue };
+            let next_cost = current + step;
+            if costs.get(&next).is_some_and(|c| *c <= next_cost) {
+                continue;
+            }
+            costs.insert(next, next_cost);
+            queue.push(Reverse((next_cost, next.x, next.y)));
+        }
+    }
+    costs
+}
@@ -0,0 +1,298 @@
+//! Turn module
+//! Actors gain energy every tick depending on their speed, and act when they
+//! have enough. A fast actor can act several times before a slow one moves
+//! again. The order only depends on the actors and their speeds, so the same
+//! level always plays out in the same order
+
+use std::{cmp::Reverse, collections::HashMap};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{player::Player, GameState, PlaySet};
+
+/// Energy spent on each action
+pub const ACTION_COST: i32 = 12;
+/// Speed of the player, acting once per tick
+pub const NORMAL_SPEED: u32 = 12;
+
+// ······
+// Plugin
+// ······
+
+/// Turn order
+/// Keeps every entity with `Speed` in the `Scheduler`
+pub struct TurnPlugin;
+
+impl Plugin for TurnPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Scheduler>()
+            .add_systems(OnExit(GameState::Play), clear_actors)
+            .add_systems(
+                Update,
+                (remove_actors, add_actors).chain().in_set(PlaySet::Tick),
+            );
+    }
+}
+
+// ··········
+// Components
+// ··········
+
+/// Energy gained each tick
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Speed(pub u32);
+
+// ·········
+// Resources
+// ·········
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+struct Actor {
+    entity: Entity,
+    speed: u32,
+    energy: i32,
+    /// When it last acted or joined, breaks ties in favour of the oldest
+    last: u64,
+}
+
+/// Decides who acts next
+#[derive(Resource, Clone, Default, Debug, Serialize, Deserialize)]
+pub struct Scheduler {
+    actors: Vec<Actor>,
+    clock: u64,
+}
+
+impl Scheduler {
+    /// Adds an actor with no energy, it acts after the ones already waiting
+    pub fn insert(&mut self, entity: Entity, speed: u32) {
+        self.remove(entity);
+        self.clock += 1;
+        self.actors.push(Actor {
+            entity,
+            speed,
+            energy: 0,
+            last: self.clock,
+        });
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        self.actors.retain(|actor| actor.entity != entity);
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.actors.iter().any(|actor| actor.entity == entity)
+    }
+
+    /// Gives the places of some actors to other entities
+    /// They are replaced at once, so the new entities can have the same ids as
+    /// old ones that are replaced too
+    pub fn replace(&mut self, entities: &HashMap<Entity, Entity>) {
+        for actor in self.actors.iter_mut() {
+            if let Some(new) = entities.get(&actor.entity) {
+                actor.entity = *new;
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Uses the energy of an action, even if it wasn't its turn
+    pub fn spend(&mut self, entity: Entity) {
+        self.clock += 1;
+        if let Some(actor) = self.actors.iter_mut().find(|actor| actor.entity == entity) {
+            actor.energy -= ACTION_COST;
+            actor.last = self.clock;
+        }
+    }
+
+    /// Returns who acts now and spends its energy
+    /// Time moves forward until someone has enough energy to act. `None` if
+    /// nobody will ever act
+    pub fn pop(&mut self) -> Option<Entity> {
+        if self.actors.iter().all(|actor| actor.speed == 0) {
+            return None;
+        }
+        loop {
+            if let Some(entity) = self.ready() {
+                self.spend(entity);
+                return Some(entity);
+            }
+            for actor in self.actors.iter_mut() {
+                actor.energy += actor.speed as i32;
+            }
+        }
+    }
+
+    /// Who acts next, without changing anything
+    pub fn peek(&self) -> Option<Entity> {
+        self.clone().pop()
+    }
+
+    /// The next actions in order, an actor can appear several times
+    pub fn upcoming(&self, count: usize) -> Vec<Entity> {
+        let mut scheduler = self.clone();
+        (0..count).map_while(|_| scheduler.pop()).collect()
+    }
+
+    /// The actor with the most energy, if it has enough to act
+    fn ready(&self) -> Option<Entity> {
+        self.actors
+            .iter()
+            .filter(|actor| actor.energy >= ACTION_COST)
+            .min_by_key(|actor| (Reverse(actor.energy), actor.last))
+            .map(|actor| actor.entity)
+    }
+}
+
+// ·······
+// Systems
+// ·······
+
+/// Adds new actors, the player after the rest so the others win the ties
+/// and act right after its first move
+/// Actors that were already placed, like the ones restored by an undo, keep
+/// their place
+fn add_actors(
+    actors: Query<(Entity, &Speed, Has<Player>), Added<Speed>>,
+    mut scheduler: ResMut<Scheduler>,
+) {
+    let mut players = Vec::new();
+    for (entity, speed, is_player) in actors.iter() {
+        if scheduler.contains(entity) {
+            continue;
+        }
+        match is_player {
+            true => players.push((entity, speed.0)),
+            false => scheduler.insert(entity, speed.0),
+        }
+    }
+    for (entity, speed) in players {
+        scheduler.insert(entity, speed);
+    }
+}
+
+fn remove_actors(mut removed: RemovedComponents<Speed>, mut scheduler: ResMut<Scheduler>) {
+    for entity in removed.read() {
+        scheduler.remove(entity);
+    }
+}
+
+fn clear_actors(mut scheduler: ResMut<Scheduler>) {
+    scheduler.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYER: Entity = Entity::from_raw(0);
+    const DOG: Entity = Entity::from_raw(1);
+    const ELDER: Entity = Entity::from_raw(2);
+
+    /// A level with a dog and an elder, added like `add_actors` does
+    fn scheduler() -> Scheduler {
+        let mut scheduler = Scheduler::default();
+        scheduler.insert(DOG, NORMAL_SPEED * 2);
+        scheduler.insert(ELDER, NORMAL_SPEED / 2);
+        scheduler.insert(PLAYER, NORMAL_SPEED);
+        scheduler
+    }
+
+    #[test]
+    fn faster_actors_act_more_often() {
+        let order = scheduler().upcoming(12);
+        assert_eq!(order, [
+            DOG, PLAYER, DOG, DOG, ELDER, PLAYER, DOG, DOG, PLAYER, DOG, DOG, ELDER
+        ]);
+
+        // Twice for the dog and every other turn for the elder
+        let turns: Vec<_> = order
+            .split(|entity| *entity == PLAYER)
+            .skip(1)
+            .map(|turn| {
+                (
+                    turn.iter().filter(|e| **e == DOG).count(),
+                    turn.iter().filter(|e| **e == ELDER).count(),
+                )
+            })
+            .collect();
+        assert_eq!(turns, [(2, 1), (2, 0), (2, 1)]);
+    }
+
+    #[test]
+    fn peek_and_upcoming_match_pop() {
+        let mut scheduler = scheduler();
+        let upcoming = scheduler.upcoming(8);
+        for expected in upcoming {
+            assert_eq!(scheduler.peek(), Some(expected));
+            assert_eq!(scheduler.peek(), Some(expected));
+            assert_eq!(scheduler.pop(), Some(expected));
+        }
+
+        assert_eq!(Scheduler::default().pop(), None);
+        let mut still = Scheduler::default();
+        still.insert(PLAYER, 0);
+        assert_eq!(still.peek(), None);
+    }
+
+    #[test]
+    fn ties_go_to_the_oldest() {
+        let mut scheduler = Scheduler::default();
+        scheduler.insert(DOG, NORMAL_SPEED);
+        scheduler.insert(ELDER, NORMAL_SPEED);
+        scheduler.insert(PLAYER, NORMAL_SPEED);
+
+        let order = scheduler.upcoming(6);
+        assert_eq!(order, [
+            DOG, ELDER, PLAYER, DOG, ELDER, PLAYER
+        ]);
+        assert_eq!(scheduler.upcoming(6), order);
+
+        // Acting out of turn puts an actor at the back
+        scheduler.spend(DOG);
+        assert_eq!(scheduler.upcoming(3), [
+            ELDER, PLAYER, DOG
+        ]);
+    }
+
+    #[test]
+    fn replaced_actors_keep_their_place() {
+        let mut scheduler = scheduler();
+        scheduler.pop();
+        let before = scheduler.upcoming(6);
+
+        // Swapped at once, so each one takes the place of the other
+        scheduler.replace(&HashMap::from([
+            (DOG, ELDER),
+            (ELDER, DOG),
+        ]));
+        let swap = |entity| {
+            if entity == DOG {
+                ELDER
+            } else if entity == ELDER {
+                DOG
+            } else {
+                entity
+            }
+        };
+        let after: Vec<_> = before.into_iter().map(swap).collect();
+        assert_eq!(scheduler.upcoming(6), after);
+    }
+
+    #[test]
+    fn removed_actors_stop_acting() {
+        let mut scheduler = scheduler();
+        scheduler.remove(DOG);
+        assert!(!scheduler.contains(DOG));
+        assert_eq!(scheduler.upcoming(4), [
+            PLAYER, ELDER, PLAYER, PLAYER
+        ]);
+
+        scheduler.clear();
+        assert!(!scheduler.contains(PLAYER));
+        assert_eq!(scheduler.pop(), None);
+    }
+}
@@ -12,6 +12,7 @@ pub mod loading;
 #[cfg(feature = "menu")]
 pub mod menu;
 pub mod shop;
+pub mod toast;
 #[cfg(feature = "tts")]
 pub mod tts;
 pub mod widgets;
@@ -33,6 +34,7 @@ impl Plugin for UiPlugin {
             confirm::ConfirmPlugin,
             gui::GuiPlugin,
             shop::ShopPlugin,
+            toast::ToastPlugin,
         ))
         .add_systems(OnExit(GameState::Startup), init);
 
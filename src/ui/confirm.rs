@@ -4,7 +4,7 @@ use sickle_ui::prelude::*;
 
 use crate::{
     assets::CoreAssets,
-    data::{GameOptions, Persistent, SaveData},
+    data::{history, lose_run, GameOptions, Persistent, SaveData},
     ui::{
         widgets::{UiButtonWidget, UiTextWidget},
         UiRootContainer, UI_GAP,
@@ -31,7 +31,7 @@ impl Plugin for ConfirmPlugin {
             )
             .add_systems(
                 OnEnter(PlayState::GameOver),
-                confirm_game_over,
+                confirm_game_over.before(lose_run),
             )
             .add_systems(
                 Update,
@@ -170,12 +170,13 @@ fn confirm_level(
         .insert(StateScoped(PlayState::ToLevel));
 }
 
+/// Shows the money that will be lost, so it runs before it is taken
 fn confirm_game_over(
     mut cmd: Commands,
     root: Query<Entity, With<UiRootContainer>>,
     assets: Res<CoreAssets>,
     options: Res<Persistent<GameOptions>>,
-    mut save_data: ResMut<Persistent<SaveData>>,
+    save_data: Res<Persistent<SaveData>>,
 ) {
     let Ok(root) = root.get_single() else { return };
 
@@ -209,9 +210,6 @@ fn confirm_game_over(
                     assets.font.clone(),
                 );
 
-                save_data.money /= 2;
-                save_data.deaths += 1;
-
                 column.row(|row| {
                     row.style()
                         .width(Val::Percent(100.))
@@ -294,13 +292,7 @@ fn confirm_game_won(
                 );
 
                 column.text(
-                    format!(
-                        "score {}",
-                        (save_data.levels_completed as i32 + 1)
-                            * save_data.enemies_killed as i32
-                            * 100
-                            - save_data.deaths as i32 * 200
-                    ),
+                    format!("score {}", history::score(&save_data)),
                     assets.font.clone(),
                 );
 
@@ -328,7 +320,6 @@ fn handle_buttons(
     mut nav_event_reader: EventReader<NavEvent>,
     mut next_state: ResMut<NextState<GameState>>,
     mut next_play_state: ResMut<NextState<PlayState>>,
-    mut save_data: ResMut<Persistent<SaveData>>,
 ) {
     for event in nav_event_reader.read() {
         if let NavEvent::NoChanges {
@@ -340,10 +331,7 @@ fn handle_buttons(
 
             match buttons {
                 ConfirmButton::Shop => next_state.set(GameState::Shop),
-                ConfirmButton::Level => {
-                    next_state.set(GameState::LevelTransition);
-                    save_data.levels_completed += 1;
-                },
+                ConfirmButton::Level => next_state.set(GameState::LevelTransition),
                 ConfirmButton::Back => next_play_state.set(PlayState::Play),
                 ConfirmButton::GameOver => {
                     next_state.set(GameState::Shop);
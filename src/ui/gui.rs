@@ -3,8 +3,8 @@ use sickle_ui::prelude::*;
 
 use super::UI_GAP;
 use crate::{
-    assets::{CoreAssets, SpriteAssets, ATLAS_SIZE},
-    data::{max_battery, max_range, GameOptions, Persistent, SaveData},
+    assets::{CoreAssets, SpriteAssets, SpriteCatalogue},
+    data::{max_range, Balance, GameOptions, Persistent, SaveData},
     enemy::Element,
     ui::{widgets::UiTextWidget, UiRootContainer},
     PlaySet, PlayState, SCALE,
@@ -44,7 +44,9 @@ enum DisplayType {
 
 #[derive(Component)]
 struct Display {
-    index: usize,
+    /// Name in the sprite catalogue, its cells are the frames from full to
+    /// empty
+    sprite: &'static str,
     display: DisplayType,
     data: Option<u32>,
 }
@@ -58,6 +60,7 @@ fn init(
     root: Query<Entity, With<UiRootContainer>>,
     assets: Res<CoreAssets>,
     sprite_assets: Res<SpriteAssets>,
+    sprites: Res<SpriteCatalogue>,
     options: Res<Persistent<GameOptions>>,
     save_data: Res<Persistent<SaveData>>,
 ) {
@@ -65,17 +68,17 @@ fn init(
 
     let displays = [
         Display {
-            index: 22 * ATLAS_SIZE.0,
+            sprite: "hud.connection",
             display: DisplayType::Connection,
             data: None,
         },
         Display {
-            index: 22 * ATLAS_SIZE.0 + 4,
+            sprite: "hud.battery",
             display: DisplayType::Battery,
             data: None,
         },
         Display {
-            index: 10 * ATLAS_SIZE.0 + 33,
+            sprite: "hud.money",
             display: DisplayType::Money,
             data: Some(save_data.money),
         },
@@ -83,22 +86,22 @@ fn init(
 
     let attacks = [
         Display {
-            index: 7 * ATLAS_SIZE.0 + 34,
+            sprite: "attack.basic",
             display: DisplayType::Attack(Element::Basic),
             data: None,
         },
         Display {
-            index: 10 * ATLAS_SIZE.0 + 15,
+            sprite: "attack.fire",
             display: DisplayType::Attack(Element::Fire),
             data: Some(save_data.fire_uses),
         },
         Display {
-            index: 13 * ATLAS_SIZE.0 + 32,
+            sprite: "attack.water",
             display: DisplayType::Attack(Element::Water),
             data: Some(save_data.water_uses),
         },
         Display {
-            index: ATLAS_SIZE.0 + 3,
+            sprite: "attack.grass",
             display: DisplayType::Attack(Element::Grass),
             data: Some(save_data.grass_uses),
         },
@@ -123,12 +126,13 @@ fn init(
                                 height: Val::Px(16. * SCALE),
                                 ..default()
                             },
-                            image: UiImage::new(sprite_assets.one_bit.clone()),
+                            image: UiImage::new(sprite_assets.one_bit.clone())
+                                .with_color(sprites.get(display.sprite).tint()),
                             ..default()
                         },
                         TextureAtlas {
                             layout: sprite_assets.one_bit_atlas.clone(),
-                            index: display.index,
+                            index: sprites.index(display.sprite),
                         },
                         display,
                     ),
@@ -175,12 +179,13 @@ fn init(
                                 height: Val::Px(16. * SCALE),
                                 ..default()
                             },
-                            image: UiImage::new(sprite_assets.one_bit.clone()),
+                            image: UiImage::new(sprite_assets.one_bit.clone())
+                                .with_color(sprites.get(att.sprite).tint()),
                             ..default()
                         },
                         TextureAtlas {
                             layout: sprite_assets.one_bit_atlas.clone(),
-                            index: att.index,
+                            index: sprites.index(att.sprite),
                         },
                         att,
                     ),
@@ -205,7 +210,9 @@ fn update_displays(
         &Display,
     )>,
     mut text: Query<&mut Text>,
+    sprites: Res<SpriteCatalogue>,
     save_data: Res<Persistent<SaveData>>,
+    balance: Res<Balance>,
 ) {
     for (mut atlas, background, children, display) in displays.iter_mut() {
         let percent = match &display.display {
@@ -216,7 +223,7 @@ fn update_displays(
                     / 4.
             },
             DisplayType::Battery => {
-                1. - save_data.battery as f32 / max_battery(save_data.battery_level) as f32
+                1. - save_data.battery as f32 / balance.max_battery(save_data.battery_level) as f32
             },
             DisplayType::Attack(ref element) => {
                 if let Some(mut color) = background {
@@ -232,7 +239,7 @@ fn update_displays(
             _ => 0.,
         };
         let offset = (percent.clamp(0., 0.99) * 4.).floor() as usize;
-        atlas.index = display.index + offset;
+        atlas.index = sprites.get(display.sprite).frame(offset);
         let Some(children) = children else { continue };
         for child in children {
             let Ok(mut text) = text.get_mut(*child) else { continue };
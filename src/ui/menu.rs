@@ -3,14 +3,22 @@
 use bevy::prelude::*;
 use bevy_alt_ui_navigation_lite::prelude::*;
 
-#[cfg(feature = "tts")]
-use crate::data::{GameOptions, Persistent};
-use crate::PlayState;
+use crate::{
+    data::{history::RecordSort, GameOptions, Persistent, SlotEvent},
+    PlayState,
+};
 
+mod achievements;
 mod main;
 mod mappings;
 pub mod navigation;
 mod options;
+mod records;
+mod slots;
+
+/// Choices for the movement repeat options, in seconds
+const REPEAT_DELAYS: [f32; 4] = [0.15, 0.25, 0.4, 0.6];
+const REPEAT_RATES: [f32; 4] = [0.08, 0.15, 0.25, 0.4];
 
 // ······
 // Plugin
@@ -24,6 +32,8 @@ impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app.add_sub_state::<MenuState>()
             .enable_state_scoped_entities::<MenuState>()
+            .init_resource::<DeleteRequest>()
+            .init_resource::<RecordsSort>()
             .add_plugins(navigation::NavigationPlugin)
             .add_systems(OnEnter(MenuState::Main), main::open)
             .add_systems(
@@ -34,6 +44,15 @@ impl Plugin for MenuPlugin {
                 OnEnter(MenuState::Mappings),
                 mappings::open,
             )
+            .add_systems(OnEnter(MenuState::Slots), slots::open)
+            .add_systems(
+                OnEnter(MenuState::Records),
+                records::open,
+            )
+            .add_systems(
+                OnEnter(MenuState::Achievements),
+                achievements::open,
+            )
             .add_systems(
                 OnEnter(MenuState::Refresh),
                 refresh_state,
@@ -55,10 +74,15 @@ pub(super) enum MenuState {
     #[default]
     Main,
     /// Menu screen to customize game options
-    /// (There are no options at the moment)
     Options,
     /// Menu screen to view keys assigned to actions
     Mappings,
+    /// Menu screen to pick, copy and delete save slots
+    Slots,
+    /// Menu screen with the best runs
+    Records,
+    /// Menu screen with every achievement
+    Achievements,
     /// Refresh the menu state by exiting and entering again
     /// Uses `MenuRefreshState` to indicate the next state
     Refresh,
@@ -75,9 +99,29 @@ enum MenuButton {
     Play,
     /// See other options, transitions to `MenuState::Options`
     Options,
+    /// See the save slots, transitions to `MenuState::Slots`
+    Saves,
+    /// Play a save slot, switching to it if it isn't the active one
+    Slot(usize),
+    /// Copy a save slot to the first empty one
+    CopySlot(usize),
+    /// Delete a save slot, it needs to be pressed twice
+    DeleteSlot(usize),
+    /// See the best runs, transitions to `MenuState::Records`
+    Records,
+    /// Change the order of the records
+    SortRecords,
+    /// See the achievements, transitions to `MenuState::Achievements`
+    Achievements,
     /// Toggle text to speech
     #[cfg(feature = "tts")]
     Speech,
+    /// Cycle how long a direction is held before it repeats
+    RepeatDelay,
+    /// Cycle the time between repeated steps
+    RepeatRate,
+    /// Toggle unlimited undo
+    Undo,
     /// Remap keys, transitions to `MenuState::Mappings`
     Mappings,
     /// Exit the game or go back a menu
@@ -91,6 +135,18 @@ enum MenuButton {
 #[derive(Component)]
 struct MenuRefreshState(MenuState);
 
+// ·········
+// Resources
+// ·········
+
+/// Slot that will be deleted if its delete button is pressed again
+#[derive(Resource, Default)]
+struct DeleteRequest(Option<usize>);
+
+/// Order of the records screen
+#[derive(Resource, Default)]
+struct RecordsSort(RecordSort);
+
 // ·······
 // Systems
 // ·······
@@ -100,14 +156,17 @@ struct MenuRefreshState(MenuState);
 /// wants to go back We are not using the bevy Interaction system, we are using
 /// NavEvents instead for accesibility and convenience
 fn handle_buttons(
-    #[cfg(feature = "tts")] mut cmd: Commands,
+    mut cmd: Commands,
     buttons: Query<&MenuButton>,
-    #[cfg(feature = "tts")] mut options: ResMut<Persistent<GameOptions>>,
+    mut options: ResMut<Persistent<GameOptions>>,
+    mut delete_request: ResMut<DeleteRequest>,
+    mut records_sort: ResMut<RecordsSort>,
     mut next_state: ResMut<NextState<PlayState>>,
     curr_menu_state: Res<State<MenuState>>,
     mut next_menu_state: ResMut<NextState<MenuState>>,
     mut nav_event_reader: EventReader<NavEvent>,
     mut nav_request_writer: EventWriter<NavRequest>,
+    mut slot_writer: EventWriter<SlotEvent>,
     mut app_exit_writer: EventWriter<AppExit>,
 ) {
     for event in nav_event_reader.read() {
@@ -128,6 +187,49 @@ fn handle_buttons(
                     MenuButton::Options => {
                         next_menu_state.set(MenuState::Options);
                     },
+                    MenuButton::Saves => {
+                        delete_request.0 = None;
+                        next_menu_state.set(MenuState::Slots);
+                    },
+                    MenuButton::Slot(slot) => {
+                        slot_writer.send(SlotEvent::Switch(*slot));
+                    },
+                    MenuButton::CopySlot(slot) => {
+                        slot_writer.send(SlotEvent::Copy(*slot));
+                        delete_request.0 = None;
+                        next_menu_state.set(MenuState::Refresh);
+                        cmd.spawn((
+                            MenuRefreshState(MenuState::Slots),
+                            StateScoped(MenuState::Refresh),
+                        ));
+                    },
+                    MenuButton::DeleteSlot(slot) => {
+                        if delete_request.0 == Some(*slot) {
+                            slot_writer.send(SlotEvent::Delete(*slot));
+                            delete_request.0 = None;
+                        } else {
+                            delete_request.0 = Some(*slot);
+                        }
+                        next_menu_state.set(MenuState::Refresh);
+                        cmd.spawn((
+                            MenuRefreshState(MenuState::Slots),
+                            StateScoped(MenuState::Refresh),
+                        ));
+                    },
+                    MenuButton::Records => {
+                        next_menu_state.set(MenuState::Records);
+                    },
+                    MenuButton::Achievements => {
+                        next_menu_state.set(MenuState::Achievements);
+                    },
+                    MenuButton::SortRecords => {
+                        records_sort.0 = records_sort.0.next();
+                        next_menu_state.set(MenuState::Refresh);
+                        cmd.spawn((
+                            MenuRefreshState(MenuState::Records),
+                            StateScoped(MenuState::Refresh),
+                        ));
+                    },
                     #[cfg(feature = "tts")]
                     MenuButton::Speech => {
                         let _ = options.update(|options| {
@@ -139,6 +241,36 @@ fn handle_buttons(
                             StateScoped(MenuState::Refresh),
                         ));
                     },
+                    MenuButton::RepeatDelay => {
+                        let _ = options.update(|options| {
+                            options.repeat_delay = cycle(options.repeat_delay, &REPEAT_DELAYS);
+                        });
+                        next_menu_state.set(MenuState::Refresh);
+                        cmd.spawn((
+                            MenuRefreshState(MenuState::Options),
+                            StateScoped(MenuState::Refresh),
+                        ));
+                    },
+                    MenuButton::RepeatRate => {
+                        let _ = options.update(|options| {
+                            options.repeat_rate = cycle(options.repeat_rate, &REPEAT_RATES);
+                        });
+                        next_menu_state.set(MenuState::Refresh);
+                        cmd.spawn((
+                            MenuRefreshState(MenuState::Options),
+                            StateScoped(MenuState::Refresh),
+                        ));
+                    },
+                    MenuButton::Undo => {
+                        let _ = options.update(|options| {
+                            options.unlimited_undo = !options.unlimited_undo;
+                        });
+                        next_menu_state.set(MenuState::Refresh);
+                        cmd.spawn((
+                            MenuRefreshState(MenuState::Options),
+                            StateScoped(MenuState::Refresh),
+                        ));
+                    },
                     MenuButton::Mappings => {
                         next_menu_state.set(MenuState::Mappings);
                     },
@@ -161,6 +293,9 @@ fn handle_buttons(
                 MenuState::Main => next_state.set(PlayState::Play),
                 MenuState::Options => next_menu_state.set(MenuState::Main),
                 MenuState::Mappings => next_menu_state.set(MenuState::Options),
+                MenuState::Slots => next_menu_state.set(MenuState::Main),
+                MenuState::Records => next_menu_state.set(MenuState::Main),
+                MenuState::Achievements => next_menu_state.set(MenuState::Main),
                 MenuState::Refresh => {},
             },
             _ => {},
@@ -178,3 +313,17 @@ fn refresh_state(
     };
     next_menu_state.set(next.0.clone());
 }
+
+// ·······
+// Helpers
+// ·······
+
+/// The choice after the closest one to a value, wrapping around
+fn cycle(value: f32, choices: &[f32]) -> f32 {
+    let closest = choices
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| (*a - value).abs().total_cmp(&(*b - value).abs()))
+        .map_or(0, |(i, _)| i);
+    choices[(closest + 1) % choices.len()]
+}
@@ -0,0 +1,76 @@
+//! Achievements menu submodule
+
+use bevy::prelude::*;
+use sickle_ui::prelude::*;
+
+use crate::{
+    achievement::{AchievementList, AchievementProgress},
+    assets::CoreAssets,
+    camera::BACKGROUND_LUMINANCE,
+    data::{GameOptions, Persistent},
+    ui::{
+        menu::{MenuButton, MenuState},
+        widgets::{UiButtonWidget, UiTextWidget},
+        UiRootContainer,
+    },
+};
+
+// ·······
+// Systems
+// ·······
+
+/// Achievements menu screen
+/// Every achievement and what it asks for, marking the locked ones
+pub(super) fn open(
+    mut cmd: Commands,
+    root: Query<Entity, With<UiRootContainer>>,
+    assets: Res<CoreAssets>,
+    options: Res<Persistent<GameOptions>>,
+    list: Res<AchievementList>,
+    progress: Res<Persistent<AchievementProgress>>,
+) {
+    let Ok(root) = root.get_single() else { return };
+
+    let unlocked = list
+        .achievements
+        .iter()
+        .filter(|achievement| progress.unlocked.contains_key(&achievement.id))
+        .count();
+
+    cmd.ui_builder(root)
+        .column(|column| {
+            column
+                .style()
+                .width(Val::Percent(100.))
+                .align_items(AlignItems::Center)
+                .justify_content(JustifyContent::Center)
+                .row_gap(Val::Px(16.));
+
+            column.title(
+                format!(
+                    "Achievements {}/{}",
+                    unlocked,
+                    list.achievements.len()
+                ),
+                assets.font.clone(),
+            );
+
+            for achievement in &list.achievements {
+                let name = match progress.unlocked.contains_key(&achievement.id) {
+                    true => achievement.name.clone(),
+                    false => format!("{} (locked)", achievement.name),
+                };
+                column.text(
+                    format!("{}: {}", name, achievement.description),
+                    assets.font.clone(),
+                );
+            }
+
+            column.button(MenuButton::ExitOrBack, |button| {
+                button.text("Back".into(), assets.font.clone());
+            });
+        })
+        .insert(StateScoped(MenuState::Achievements))
+        .style()
+        .background_color(options.base_color.with_luminance(BACKGROUND_LUMINANCE));
+}
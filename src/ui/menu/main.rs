@@ -48,6 +48,21 @@ pub(super) fn open(
                 button.text("PLAY".into(), assets.font.clone());
             });
 
+            column.button(MenuButton::Saves, |button| {
+                button.text("SAVES".into(), assets.font.clone());
+            });
+
+            column.button(MenuButton::Records, |button| {
+                button.text("RECORDS".into(), assets.font.clone());
+            });
+
+            column.button(MenuButton::Achievements, |button| {
+                button.text(
+                    "ACHIEVEMENTS".into(),
+                    assets.font.clone(),
+                );
+            });
+
             column.button(MenuButton::Options, |button| {
                 button.text("OPTIONS".into(), assets.font.clone());
             });
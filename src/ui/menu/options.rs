@@ -55,6 +55,45 @@ pub(super) fn open(
                     );
                 });
 
+            column
+                .option_row(
+                    MenuButton::RepeatDelay,
+                    "Repeat delay".into(),
+                    assets.font.clone(),
+                )
+                .option_button(|button| {
+                    button.text(
+                        format!("{:.2}s", options.repeat_delay),
+                        assets.font.clone(),
+                    );
+                });
+
+            column
+                .option_row(
+                    MenuButton::RepeatRate,
+                    "Repeat rate".into(),
+                    assets.font.clone(),
+                )
+                .option_button(|button| {
+                    button.text(
+                        format!("{:.2}s", options.repeat_rate),
+                        assets.font.clone(),
+                    );
+                });
+
+            column
+                .option_row(
+                    MenuButton::Undo,
+                    "Undo".into(),
+                    assets.font.clone(),
+                )
+                .option_button(|button| {
+                    button.text(
+                        (if options.unlimited_undo { "Unlimited" } else { "Limited" }).into(),
+                        assets.font.clone(),
+                    );
+                });
+
             column
                 .option_row(
                     MenuButton::Mappings,
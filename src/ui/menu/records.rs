@@ -0,0 +1,97 @@
+//! Records menu submodule
+
+use bevy::prelude::*;
+use sickle_ui::prelude::*;
+
+use crate::{
+    assets::CoreAssets,
+    camera::BACKGROUND_LUMINANCE,
+    data::{
+        history::{RunHistory, RunRecord, MAX_RECORDS},
+        now, GameOptions, Persistent,
+    },
+    ui::{
+        menu::{slots::time_ago, MenuButton, MenuState, RecordsSort},
+        widgets::{UiButtonWidget, UiTextWidget},
+        UiRootContainer, UI_GAP,
+    },
+};
+
+// ·······
+// Systems
+// ·······
+
+/// Records menu screen
+/// Shows the best runs in the history, sorted by the chosen order
+pub(super) fn open(
+    mut cmd: Commands,
+    root: Query<Entity, With<UiRootContainer>>,
+    assets: Res<CoreAssets>,
+    options: Res<Persistent<GameOptions>>,
+    history: Res<Persistent<RunHistory>>,
+    sort: Res<RecordsSort>,
+) {
+    let Ok(root) = root.get_single() else { return };
+
+    let mut runs = history.runs.clone();
+    sort.0.sort(&mut runs);
+
+    cmd.ui_builder(root)
+        .column(|column| {
+            column
+                .style()
+                .width(Val::Percent(100.))
+                .align_items(AlignItems::Center)
+                .justify_content(JustifyContent::Center)
+                .row_gap(UI_GAP);
+
+            column.title("Records".into(), assets.font.clone());
+
+            if runs.is_empty() {
+                column.text(
+                    "No runs have got to the artifact yet".into(),
+                    assets.font.clone(),
+                );
+            }
+            for (i, run) in runs.iter().take(MAX_RECORDS).enumerate() {
+                column.text(summary(i, run), assets.font.clone());
+            }
+
+            column.button(MenuButton::SortRecords, |button| {
+                button.text(
+                    format!("Sort by {}", sort.0.name()),
+                    assets.font.clone(),
+                );
+            });
+
+            column.button(MenuButton::ExitOrBack, |button| {
+                button.text("Back".into(), assets.font.clone());
+            });
+        })
+        .insert(StateScoped(MenuState::Records))
+        .style()
+        .background_color(options.base_color.with_luminance(BACKGROUND_LUMINANCE));
+}
+
+// ·······
+// Helpers
+// ·······
+
+/// A row of the table with the position and stats of a run
+fn summary(i: usize, run: &RunRecord) -> String {
+    let mut summary = format!(
+        "{}. {} points, {} kills, {} levels, {} deaths",
+        i + 1,
+        run.score,
+        run.kills,
+        run.levels_completed,
+        run.deaths
+    );
+    if run.date > 0 {
+        summary += &format!(
+            ", {}",
+            time_ago(now().saturating_sub(run.date))
+        );
+    }
+    summary
+}
@@ -0,0 +1,140 @@
+//! Save slots menu submodule
+
+use bevy::prelude::*;
+use sickle_ui::prelude::*;
+
+use crate::{
+    assets::CoreAssets,
+    camera::BACKGROUND_LUMINANCE,
+    data::{now, peek_slot, GameOptions, Persistent, SaveData, SAVE_SLOTS},
+    ui::{
+        menu::{DeleteRequest, MenuButton, MenuState},
+        widgets::{UiButtonWidget, UiTextWidget},
+        UiRootContainer, UI_GAP,
+    },
+    AppConfig,
+};
+
+const SLOT_WIDTH: Val = Val::Px(480.);
+const SLOT_HEIGHT: Val = Val::Px(96.);
+const ACTION_WIDTH: Val = Val::Px(160.);
+
+// ·······
+// Systems
+// ·······
+
+/// Save slots menu screen
+/// Each slot can be played, copied to an empty one or deleted, except the one
+/// being played that can't be deleted
+pub(super) fn open(
+    mut cmd: Commands,
+    root: Query<Entity, With<UiRootContainer>>,
+    assets: Res<CoreAssets>,
+    config: Res<AppConfig>,
+    options: Res<Persistent<GameOptions>>,
+    save_data: Res<Persistent<SaveData>>,
+    delete_request: Res<DeleteRequest>,
+) {
+    let Ok(root) = root.get_single() else { return };
+
+    cmd.ui_builder(root)
+        .column(|column| {
+            column
+                .style()
+                .width(Val::Percent(100.))
+                .align_items(AlignItems::Center)
+                .justify_content(JustifyContent::Center)
+                .row_gap(UI_GAP);
+
+            column.title("Saves".into(), assets.font.clone());
+
+            for slot in 0..SAVE_SLOTS {
+                let active = slot == options.active_slot;
+                let data = match active {
+                    true => SaveData::clone(&save_data),
+                    false => peek_slot(&config, slot),
+                };
+
+                column.row(|row| {
+                    row.style().column_gap(Val::Px(8.));
+
+                    row.button(MenuButton::Slot(slot), |button| {
+                        button.text(
+                            summary(slot, active, &data),
+                            assets.font.clone(),
+                        );
+                    })
+                    .style()
+                    .width(SLOT_WIDTH)
+                    .height(SLOT_HEIGHT);
+
+                    if data.is_new() {
+                        return;
+                    }
+                    row.button(MenuButton::CopySlot(slot), |button| {
+                        button.text("Copy".into(), assets.font.clone());
+                    })
+                    .style()
+                    .width(ACTION_WIDTH)
+                    .height(SLOT_HEIGHT);
+
+                    if active {
+                        return;
+                    }
+                    let delete = match delete_request.0 {
+                        Some(request) if request == slot => "Sure?",
+                        _ => "Delete",
+                    };
+                    row.button(MenuButton::DeleteSlot(slot), |button| {
+                        button.text(delete.into(), assets.font.clone());
+                    })
+                    .style()
+                    .width(ACTION_WIDTH)
+                    .height(SLOT_HEIGHT);
+                });
+            }
+
+            column.button(MenuButton::ExitOrBack, |button| {
+                button.text("Back".into(), assets.font.clone());
+            });
+        })
+        .insert(StateScoped(MenuState::Slots))
+        .style()
+        .background_color(options.base_color.with_luminance(BACKGROUND_LUMINANCE));
+}
+
+// ·······
+// Helpers
+// ·······
+
+/// Name of the slot and the progress in it
+fn summary(slot: usize, active: bool, data: &SaveData) -> String {
+    let name = match active {
+        true => format!("Slot {} (playing)", slot + 1),
+        false => format!("Slot {}", slot + 1),
+    };
+    if data.is_new() {
+        return format!("{}\nEmpty", name);
+    }
+
+    let mut summary = format!(
+        "{}\nDepth {}, {} coins, {} deaths",
+        name, data.level, data.money, data.deaths
+    );
+    if data.last_played > 0 {
+        summary += &format!(
+            "\nPlayed {}",
+            time_ago(now().saturating_sub(data.last_played))
+        );
+    }
+    summary
+}
+
+pub(super) fn time_ago(secs: u64) -> String {
+    match secs {
+        0..=59 => "just now".into(),
+        60..=3599 => format!("{} min ago", secs / 60),
+        3600..=86399 => format!("{} h ago", secs / 3600),
+        _ => format!("{} days ago", secs / 86400),
+    }
+}
@@ -3,9 +3,9 @@ use bevy_alt_ui_navigation_lite::prelude::*;
 use sickle_ui::prelude::*;
 
 use crate::{
-    assets::{CoreAssets, SpriteAssets, ATLAS_SIZE},
+    assets::{CoreAssets, SpriteAssets, SpriteCatalogue, SpriteEntry},
     camera::BACKGROUND_LUMINANCE,
-    data::{GameOptions, Persistent, RestartEvent, SaveData},
+    data::{Balance, GameOptions, Persistent, PurchaseEvent, RestartEvent, SaveData, Upgrade},
     ui::{
         menu::navigation::on_mouse_move,
         widgets::{UiButtonWidget, UiTextWidget},
@@ -16,8 +16,6 @@ use crate::{
 
 const SIZE: Val = Val::Px(16. * SCALE);
 
-const PRICE: [u32; 11] = [5, 10, 15, 30, 40, 50, 60, 70, 100, 200, 999];
-
 // ······
 // Plugin
 // ······
@@ -54,16 +52,6 @@ enum ShopButton {
     Minus(Upgrade),
 }
 
-#[derive(Clone, Copy)]
-enum Upgrade {
-    Range,
-    Battery,
-    Basic,
-    Fire,
-    Water,
-    Grass,
-}
-
 // ·······
 // Systems
 // ·······
@@ -73,8 +61,10 @@ fn init(
     root: Query<Entity, With<UiRootContainer>>,
     assets: Res<CoreAssets>,
     sprite_assets: Res<SpriteAssets>,
+    sprites: Res<SpriteCatalogue>,
     options: Res<Persistent<GameOptions>>,
     save_data: Res<Persistent<SaveData>>,
+    balance: Res<Balance>,
 ) {
     let Ok(root) = root.get_single() else { return };
 
@@ -100,12 +90,13 @@ fn init(
                             height: Val::Px(16. * SCALE),
                             ..default()
                         },
-                        image: UiImage::new(sprite_assets.one_bit.clone()),
+                        image: UiImage::new(sprite_assets.one_bit.clone())
+                            .with_color(sprites.get("hud.money").tint()),
                         ..default()
                     },
                     TextureAtlas {
                         layout: sprite_assets.one_bit_atlas.clone(),
-                        index: 10 * ATLAS_SIZE.0 + 33,
+                        index: sprites.index("hud.money"),
                     },
                 ));
 
@@ -124,32 +115,32 @@ fn init(
 
                 let upgrades = [
                     (
-                        22 * ATLAS_SIZE.0,
+                        "hud.connection",
                         save_data.range_level,
                         Upgrade::Range,
                     ),
                     (
-                        22 * ATLAS_SIZE.0 + 4,
+                        "hud.battery",
                         save_data.battery_level,
                         Upgrade::Battery,
                     ),
                     (
-                        7 * ATLAS_SIZE.0 + 34,
+                        "attack.basic",
                         save_data.attack_level,
                         Upgrade::Basic,
                     ),
                     (
-                        10 * ATLAS_SIZE.0 + 15,
+                        "attack.fire",
                         save_data.fire as usize,
                         Upgrade::Fire,
                     ),
                     (
-                        13 * ATLAS_SIZE.0 + 32,
+                        "attack.water",
                         save_data.water as usize,
                         Upgrade::Water,
                     ),
                     (
-                        ATLAS_SIZE.0 + 3,
+                        "attack.grass",
                         save_data.grass as usize,
                         Upgrade::Grass,
                     ),
@@ -166,12 +157,13 @@ fn init(
                     column.text("Upgrades".into(), assets.font.clone());
                 });
 
-                for (index, value, typ) in &upgrades[0..3] {
+                for (sprite, value, typ) in &upgrades[0..3] {
                     shop_row(
                         &mut col,
                         assets.font.clone(),
                         &sprite_assets,
-                        *index,
+                        sprites.get(sprite),
+                        &balance,
                         *value,
                         typ,
                     );
@@ -188,12 +180,13 @@ fn init(
                     column.text("Attacks".into(), assets.font.clone());
                 });
 
-                for (index, value, typ) in &upgrades[3..6] {
+                for (sprite, value, typ) in &upgrades[3..6] {
                     shop_row(
                         &mut col,
                         assets.font.clone(),
                         &sprite_assets,
-                        *index,
+                        sprites.get(sprite),
+                        &balance,
                         *value,
                         typ,
                     );
@@ -215,7 +208,9 @@ fn handle_buttons(
     buttons: Query<&ShopButton>,
     mut nav_event_reader: EventReader<NavEvent>,
     mut reset_writer: EventWriter<RestartEvent>,
+    mut purchase_writer: EventWriter<PurchaseEvent>,
     mut save_data: ResMut<Persistent<SaveData>>,
+    balance: Res<Balance>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
     for event in nav_event_reader.read() {
@@ -231,91 +226,13 @@ fn handle_buttons(
                     reset_writer.send(RestartEvent);
                     continue;
                 },
-                ShopButton::Plus(upgrade) => match upgrade {
-                    Upgrade::Range => {
-                        if save_data.range_level < 10
-                            && save_data.money >= PRICE[save_data.range_level]
-                        {
-                            save_data.money -= PRICE[save_data.range_level];
-                            save_data.range_level += 1;
-                        }
-                    },
-                    Upgrade::Battery => {
-                        if save_data.battery_level < 10
-                            && save_data.money >= PRICE[save_data.battery_level]
-                        {
-                            save_data.money -= PRICE[save_data.battery_level];
-                            save_data.battery_level += 1;
-                        }
-                    },
-                    Upgrade::Basic => {
-                        if save_data.attack_level < 10
-                            && save_data.money >= PRICE[save_data.attack_level]
-                        {
-                            save_data.money -= PRICE[save_data.attack_level];
-                            save_data.attack_level += 1;
-                        }
-                    },
-                    Upgrade::Fire => {
-                        let lvl = save_data.fire as usize;
-                        if save_data.money >= PRICE[lvl] {
-                            save_data.money -= PRICE[lvl];
-                            save_data.fire += 1;
-                        }
-                    },
-                    Upgrade::Water => {
-                        let lvl = save_data.water as usize;
-                        if save_data.money >= PRICE[lvl] {
-                            save_data.money -= PRICE[lvl];
-                            save_data.water += 1;
-                        }
-                    },
-                    Upgrade::Grass => {
-                        let lvl = save_data.grass as usize;
-                        if save_data.money >= PRICE[lvl] {
-                            save_data.money -= PRICE[lvl];
-                            save_data.grass += 1;
-                        }
-                    },
+                ShopButton::Plus(upgrade) => {
+                    if save_data.buy(*upgrade, &balance) {
+                        purchase_writer.send(PurchaseEvent(*upgrade));
+                    }
                 },
-                ShopButton::Minus(upgrade) => match upgrade {
-                    Upgrade::Range => {
-                        if save_data.range_level > 0 {
-                            save_data.range_level -= 1;
-                            save_data.money += PRICE[save_data.range_level];
-                        }
-                    },
-                    Upgrade::Battery => {
-                        if save_data.battery_level > 0 {
-                            save_data.battery_level -= 1;
-                            save_data.money += PRICE[save_data.battery_level];
-                        }
-                    },
-                    Upgrade::Basic => {
-                        if save_data.attack_level > 0 {
-                            save_data.attack_level -= 1;
-                            save_data.money += PRICE[save_data.attack_level];
-                        }
-                    },
-                    Upgrade::Fire => {
-                        if save_data.fire > 0 {
-                            save_data.fire -= 1;
-                            save_data.money += PRICE[save_data.fire as usize];
-                        }
-                    },
-                    Upgrade::Water => {
-                        if save_data.water > 0 {
-                            save_data.water -= 1;
-                            save_data.money += PRICE[save_data.water as usize];
-                        }
-                    },
-                    Upgrade::Grass => {
-                        println!("Grass");
-                        if save_data.grass > 0 {
-                            save_data.grass -= 1;
-                            save_data.money += PRICE[save_data.grass as usize];
-                        }
-                    },
+                ShopButton::Minus(upgrade) => {
+                    save_data.sell(*upgrade, &balance);
                 },
             }
 
@@ -333,7 +250,8 @@ fn shop_row(
     col: &mut UiBuilder<Entity>,
     font: Handle<Font>,
     sprite_assets: &SpriteAssets,
-    index: usize,
+    sprite: &SpriteEntry,
+    balance: &Balance,
     value: usize,
     typ: &Upgrade,
 ) {
@@ -341,7 +259,7 @@ fn shop_row(
         let mut button = row.button(ShopButton::Minus(*typ), |button| {
             let text = value
                 .checked_sub(1)
-                .map(|v| PRICE[v])
+                .map(|v| balance.price[v])
                 .unwrap_or(0)
                 .to_string();
             button.column(|column| {
@@ -381,12 +299,12 @@ fn shop_row(
                     height: SIZE,
                     ..default()
                 },
-                image: UiImage::new(sprite_assets.one_bit.clone()),
+                image: UiImage::new(sprite_assets.one_bit.clone()).with_color(sprite.tint()),
                 ..default()
             },
             TextureAtlas {
                 layout: sprite_assets.one_bit_atlas.clone(),
-                index,
+                index: sprite.frame(0),
             },
         ));
 
@@ -401,7 +319,7 @@ fn shop_row(
                     .row_gap(Val::Px(8.));
                 column.text("+".into(), font.clone());
                 if value < 10 {
-                    let text = format!("{}", PRICE[value]);
+                    let text = format!("{}", balance.price[value]);
                     column.spawn((
                         #[cfg(feature = "tts")]
                         crate::ui::tts::SpeechTag(text.clone()),
@@ -0,0 +1,94 @@
+//! Toast submodule
+//! Small notices shown in a corner for a few seconds, on top of everything
+
+use bevy::prelude::*;
+use sickle_ui::prelude::*;
+
+use crate::{
+    achievement::UnlockEvent,
+    assets::CoreAssets,
+    data::{GameOptions, Persistent},
+    ui::{widgets::UiTextWidget, UiRootContainer},
+};
+
+/// Seconds a toast stays on screen
+const TOAST_DURATION: f32 = 3.;
+
+// ······
+// Plugin
+// ······
+
+pub struct ToastPlugin;
+
+impl Plugin for ToastPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                show_unlocks.run_if(on_event::<UnlockEvent>()),
+                update_toasts,
+            ),
+        );
+    }
+}
+
+// ··········
+// Components
+// ··········
+
+#[derive(Component)]
+struct Toast(Timer);
+
+// ·······
+// Systems
+// ·······
+
+/// Announces every achievement that was unlocked
+fn show_unlocks(
+    mut cmd: Commands,
+    root: Query<Entity, With<UiRootContainer>>,
+    toasts: Query<(), With<Toast>>,
+    assets: Res<CoreAssets>,
+    options: Res<Persistent<GameOptions>>,
+    mut unlock_reader: EventReader<UnlockEvent>,
+) {
+    let Ok(root) = root.get_single() else { return };
+
+    // New toasts go below the ones that are still shown
+    for (i, unlock) in unlock_reader.read().enumerate() {
+        let top = Val::Px(16. + 112. * (toasts.iter().len() + i) as f32);
+        cmd.ui_builder(root)
+            .column(|column| {
+                column.text(
+                    "Achievement unlocked".into(),
+                    assets.font.clone(),
+                );
+                column.text(
+                    unlock.0.name.clone(),
+                    assets.font.clone(),
+                );
+            })
+            .insert((
+                Toast(Timer::from_seconds(
+                    TOAST_DURATION,
+                    TimerMode::Once,
+                )),
+                ZIndex::Global(10),
+            ))
+            .style()
+            .position_type(PositionType::Absolute)
+            .top(top)
+            .right(Val::Px(16.))
+            .padding(UiRect::all(Val::Px(12.)))
+            .background_color(options.base_color.with_luminance(0.02));
+    }
+}
+
+/// Removes the toasts once their time is up
+fn update_toasts(mut cmd: Commands, mut toasts: Query<(Entity, &mut Toast)>, time: Res<Time>) {
+    for (entity, mut toast) in toasts.iter_mut() {
+        if toast.0.tick(time.delta()).finished() {
+            cmd.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,313 @@
+//! Undo module
+//! A snapshot of the level is taken at the start of every player turn, and it
+//! is kept once the player acts. `Action::Undo` goes back to the start of the
+//! last turn, also from the ladder confirmations. Undoing is a `Command`, so it
+//! is also part of replays. Enemies are spawned again
+//! from the snapshot, and tiles only change between levels so the grid only
+//! needs its occupants updated
+
+use bevy::prelude::*;
+
+use crate::{
+    assets::SpriteAssets,
+    data::{GameOptions, Persistent, SaveData},
+    enemy::{Enemy, EnemySnapshot, SnapshotQuery},
+    input::{Action, ActionState},
+    misc::MoveTo,
+    player::{aim::Aim, Command, CommandEvent, NextCommand, Player},
+    replay::is_playing,
+    tilemap::{tile_to_pos, GameRng, Tilemap},
+    turn::Scheduler,
+    GameState, PlayState, TurnState,
+};
+
+/// Turns kept in the stack
+const MAX_SNAPSHOTS: usize = 20;
+
+// ······
+// Plugin
+// ······
+
+/// Undo
+/// Records the turns of the player and rolls them back
+pub struct UndoPlugin;
+
+impl Plugin for UndoPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<UndoStack>()
+            .add_systems(OnEnter(GameState::Play), clear_stack)
+            .add_systems(
+                OnEnter(TurnState::Player),
+                take_snapshot,
+            )
+            .add_systems(OnEnter(TurnState::Enemy), push_snapshot)
+            .add_systems(
+                OnEnter(PlayState::ToShop),
+                push_snapshot,
+            )
+            .add_systems(
+                OnEnter(PlayState::ToLevel),
+                push_snapshot,
+            )
+            .add_systems(
+                Update,
+                (read_undo.run_if(not(is_playing)), undo).chain().run_if(
+                    in_state(TurnState::Player)
+                        .or_else(in_state(PlayState::ToShop))
+                        .or_else(in_state(PlayState::ToLevel)),
+                ),
+            );
+    }
+}
+
+// ·········
+// Resources
+// ·········
+
+/// State of the level at the start of a player turn
+#[derive(Clone)]
+pub struct Snapshot {
+    pub player: IVec2,
+    pub enemies: Vec<EnemySnapshot>,
+    pub scheduler: Scheduler,
+    pub rng: GameRng,
+    pub battery: u32,
+    pub money: u32,
+    pub fire_uses: u32,
+    pub water_uses: u32,
+    pub grass_uses: u32,
+    pub enemies_killed: u32,
+}
+
+/// Snapshots of the previous turns in this level, the last one is the newest
+#[derive(Resource, Default)]
+pub struct UndoStack {
+    pub snapshots: Vec<Snapshot>,
+    /// Snapshot of the current turn, kept once the player acts
+    pending: Option<Snapshot>,
+}
+
+// ·······
+// Systems
+// ·······
+
+fn clear_stack(mut stack: ResMut<UndoStack>) {
+    *stack = UndoStack::default();
+}
+
+fn take_snapshot(
+    player: Query<&Player>,
+    enemies: SnapshotQuery,
+    scheduler: Res<Scheduler>,
+    rng: Res<GameRng>,
+    save_data: Res<Persistent<SaveData>>,
+    mut stack: ResMut<UndoStack>,
+) {
+    let Ok(player) = player.get_single() else { return };
+
+    stack.pending = Some(Snapshot {
+        player: player.pos,
+        enemies: EnemySnapshot::take_all(&enemies),
+        scheduler: scheduler.clone(),
+        rng: rng.clone(),
+        battery: save_data.battery,
+        money: save_data.money,
+        fire_uses: save_data.fire_uses,
+        water_uses: save_data.water_uses,
+        grass_uses: save_data.grass_uses,
+        enemies_killed: save_data.enemies_killed,
+    });
+}
+
+/// The player acted, so its turn can be undone
+fn push_snapshot(mut stack: ResMut<UndoStack>) {
+    let Some(snapshot) = stack.pending.take() else { return };
+    if stack.snapshots.len() >= MAX_SNAPSHOTS {
+        stack.snapshots.remove(0);
+    }
+    stack.snapshots.push(snapshot);
+}
+
+fn read_undo(input: Query<&ActionState<Action>>, mut next: ResMut<NextCommand>) {
+    let Ok(input) = input.get_single() else { return };
+    if input.just_pressed(&Action::Undo) {
+        next.0 = Some(Command::Undo);
+    }
+}
+
+fn undo(
+    mut cmd: Commands,
+    mut next: ResMut<NextCommand>,
+    mut player: Query<(Entity, &mut Player, &mut Transform)>,
+    enemies: Query<(Entity, &Enemy)>,
+    mut stack: ResMut<UndoStack>,
+    mut scheduler: ResMut<Scheduler>,
+    mut rng: ResMut<GameRng>,
+    mut tilemap: ResMut<Tilemap>,
+    mut aim: ResMut<Aim>,
+    mut save_data: ResMut<Persistent<SaveData>>,
+    options: Res<Persistent<GameOptions>>,
+    sprite_assets: Res<SpriteAssets>,
+    play_state: Res<State<PlayState>>,
+    mut next_play_state: ResMut<NextState<PlayState>>,
+    mut command_writer: EventWriter<CommandEvent>,
+) {
+    if next.0 != Some(Command::Undo) {
+        return;
+    }
+    next.0 = None;
+    if !options.unlimited_undo && save_data.undos_left == 0 {
+        return;
+    }
+    let Ok((player_entity, mut player, mut trans)) = player.get_single_mut() else {
+        return;
+    };
+    let Some(snapshot) = stack.snapshots.pop() else { return };
+    command_writer.send(CommandEvent(Command::Undo));
+
+    if !options.unlimited_undo {
+        save_data.undos_left -= 1;
+    }
+    save_data.battery = snapshot.battery;
+    save_data.money = snapshot.money;
+    save_data.fire_uses = snapshot.fire_uses;
+    save_data.water_uses = snapshot.water_uses;
+    save_data.grass_uses = snapshot.grass_uses;
+    save_data.enemies_killed = snapshot.enemies_killed;
+
+    for (entity, enemy) in enemies.iter() {
+        tilemap.set_occupant(enemy.pos, None);
+        cmd.entity(entity).despawn();
+    }
+
+    tilemap.set_occupant(player.pos, None);
+    player.pos = snapshot.player;
+    tilemap.set_occupant(player.pos, Some(player_entity));
+    trans.translation = tile_to_pos(player.pos).extend(trans.translation.z);
+    cmd.entity(player_entity).remove::<MoveTo>();
+
+    *scheduler = snapshot.scheduler.clone();
+    *rng = snapshot.rng.clone();
+    let respawned = snapshot
+        .enemies
+        .iter()
+        .cloned()
+        .map(|saved| {
+            let entity = saved.entity;
+            (
+                entity,
+                saved.respawn(&mut cmd, &sprite_assets, &mut tilemap),
+            )
+        })
+        .collect();
+    scheduler.replace(&respawned);
+    aim.0 = None;
+
+    // Back from the ladder, entering the player turn takes the snapshot again
+    match play_state.get() {
+        PlayState::Play => stack.pending = Some(snapshot),
+        _ => next_play_state.set(PlayState::Play),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        data::UNDOS_PER_RUN,
+        misc::{dir_to_vec, vec_to_dir, Direction},
+        testing::TestApp,
+    };
+
+    /// Everything an undo puts back
+    fn turn_state(game: &mut TestApp) -> (IVec2, Vec<(IVec2, f32)>, u32, u32) {
+        let save_data = game.save_data();
+        let (battery, fire_uses) = (save_data.battery, save_data.fire_uses);
+        (
+            game.player_pos(),
+            game.enemies(),
+            battery,
+            fire_uses,
+        )
+    }
+
+    #[test]
+    fn undo_goes_back_to_the_last_turn() {
+        let mut game = TestApp::new();
+        game.start_level();
+        game.save_data_mut().fire_uses = 2;
+        let start = game.player_pos();
+        let dir = game.free_direction();
+        game.step(dir);
+        let before = turn_state(&mut game);
+
+        // Somewhere other than back to the ladder
+        let pos = game.player_pos();
+        let dir = Direction::iter()
+            .find(|dir| {
+                let next = pos + dir_to_vec(dir, 1.).as_ivec2();
+                next != start && game.tilemap().cost(next) == Some(1)
+            })
+            .expect("the player is in a dead end");
+        game.step(*dir);
+        game.save_data_mut().fire_uses = 0;
+        assert_ne!(turn_state(&mut game), before);
+
+        game.act(Command::Undo);
+        assert_eq!(turn_state(&mut game), before);
+        assert_eq!(
+            game.save_data().undos_left,
+            UNDOS_PER_RUN - 1
+        );
+
+        // Undoing again goes back to the start, and then there is nothing left
+        game.act(Command::Undo);
+        assert_eq!(game.player_pos(), start);
+        game.act(Command::Undo);
+        assert_eq!(game.player_pos(), start);
+        assert_eq!(
+            game.save_data().undos_left,
+            UNDOS_PER_RUN - 2
+        );
+    }
+
+    #[test]
+    fn undo_leaves_the_ladder_confirmation() {
+        let mut game = TestApp::new();
+        game.start_level();
+        let start = game.player_pos();
+        let dir = game.free_direction();
+        game.step(dir);
+        let away = game.player_pos();
+
+        game.step(vec_to_dir(start - away).unwrap());
+        assert_eq!(
+            game.state::<PlayState>(),
+            Some(&PlayState::ToShop)
+        );
+
+        game.act(Command::Undo);
+        assert_eq!(
+            game.state::<PlayState>(),
+            Some(&PlayState::Play)
+        );
+        assert_eq!(game.player_pos(), away);
+        assert_eq!(
+            game.save_data().undos_left,
+            UNDOS_PER_RUN - 1
+        );
+    }
+
+    #[test]
+    fn undos_run_out() {
+        let mut game = TestApp::new();
+        game.start_level();
+        game.save_data_mut().undos_left = 0;
+        let dir = game.free_direction();
+        game.step(dir);
+        let after = game.player_pos();
+
+        game.act(Command::Undo);
+        assert_eq!(game.player_pos(), after);
+    }
+}